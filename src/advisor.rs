@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::sql::quote_string;
+use crate::sql::tokenize;
+use crate::sql::Token;
+use crate::table::Table;
+
+/// Queries read from `system.query_log` by `Table::recommend_keys`.
+const MAX_QUERIES: u64 = 10_000;
+
+/// Words ending a `WHERE` or `PREWHERE` clause.
+const FILTER_END: &[&str] = &[
+    "FORMAT", "GROUP", "HAVING", "INTO", "LIMIT", "ORDER", "QUALIFY", "SETTINGS", "UNION", "WINDOW",
+];
+
+/// Sorting and partition keys suggested from the filters a table is queried with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyRecommendation {
+    pub current_order_by: String,
+    pub current_partition_by: String,
+    /// Columns for `ORDER BY`, empty without filters to go by.
+    pub order_by: Vec<String>,
+    /// `toYYYYMM(<column>)` when most queries filter on a date range and the table isn't
+    /// partitioned yet.
+    pub partition_by: Option<String>,
+    /// Filtered columns, most frequent first.
+    pub filter_columns: Vec<FilterColumn>,
+    pub queries_analyzed: usize,
+}
+
+/// How often a column appears in `WHERE`/`PREWHERE` clauses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterColumn {
+    pub name: String,
+    /// Queries filtering on the column.
+    pub queries: usize,
+    /// Queries comparing it with `=` or `IN`, the rest use ranges or functions.
+    pub equality_queries: usize,
+}
+
+impl KeyRecommendation {
+    /// Whether the suggested sorting key differs from the current one.
+    pub fn differs(&self) -> bool {
+        !self.order_by.is_empty() && self.order_by.join(", ") != self.current_order_by
+    }
+
+    /// Analyzes `queries` against the columns of `schema`.
+    ///
+    /// Columns filtered by equality come first in the sorting key, most frequent first,
+    /// followed by the most frequent range-filtered column; at most three columns are
+    /// suggested. Columns filtered in less than a tenth of the queries are ignored.
+    pub fn from_queries(schema: &Schema, queries: &[String]) -> Self {
+        let column_types: HashMap<&str, &str> = schema
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.type_name.as_str()))
+            .collect();
+
+        let mut usage: HashMap<&str, (usize, usize)> = HashMap::new();
+        for query in queries {
+            let mut seen = HashSet::new();
+            let mut seen_equality = HashSet::new();
+            for (column, equality) in filter_columns(query) {
+                let Some((name, _)) = column_types.get_key_value(column) else {
+                    continue;
+                };
+                let entry = usage.entry(name).or_default();
+                if seen.insert(column) {
+                    entry.0 += 1;
+                }
+                if equality && seen_equality.insert(column) {
+                    entry.1 += 1;
+                }
+            }
+        }
+
+        let mut filter_columns: Vec<FilterColumn> = usage
+            .into_iter()
+            .map(|(name, (queries, equality_queries))| FilterColumn {
+                name: name.to_string(),
+                queries,
+                equality_queries,
+            })
+            .collect();
+        filter_columns.sort_by(|a, b| b.queries.cmp(&a.queries).then(a.name.cmp(&b.name)));
+
+        let threshold = queries.len().div_ceil(10).max(1);
+        let frequent = filter_columns.iter().filter(|c| c.queries >= threshold);
+        let (equality, range): (Vec<&FilterColumn>, Vec<&FilterColumn>) =
+            frequent.partition(|c| c.equality_queries * 2 >= c.queries);
+
+        let mut order_by: Vec<String> = equality.iter().take(2).map(|c| c.name.clone()).collect();
+        if let Some(column) = range.first() {
+            order_by.push(column.name.clone());
+        }
+        order_by.truncate(3);
+
+        let partition_by = range
+            .iter()
+            .find(|c| {
+                c.queries * 2 >= queries.len()
+                    && column_types
+                        .get(c.name.as_str())
+                        .is_some_and(|t| t.contains("Date"))
+            })
+            .map(|c| format!("toYYYYMM({})", c.name));
+
+        Self {
+            order_by,
+            partition_by,
+            filter_columns,
+            queries_analyzed: queries.len(),
+            ..Self::default()
+        }
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Suggests keys from the `SELECT`s on this table recorded in `system.query_log`.
+    ///
+    /// The session must log queries, see `Session::queries_by_comment` for the setup; otherwise
+    /// this fails with `Error::QueryLogUnavailable` rather than recommending from nothing.
+    pub fn recommend_keys(&self) -> Result<KeyRecommendation, Error> {
+        let session = self.session();
+        session.flush_query_log()?;
+
+        let qualified = match self.name().split_once('.') {
+            Some(_) => quote_string(self.name()),
+            None => format!("currentDatabase() || '.' || {}", quote_string(self.name())),
+        };
+        let rows = session.query_rows(&format!(
+            "SELECT query FROM system.query_log \
+             WHERE type = 'QueryFinish' AND query_kind = 'Select' AND has(tables, {}) \
+             ORDER BY event_time_microseconds DESC LIMIT {}",
+            qualified, MAX_QUERIES
+        ))?;
+        let queries: Vec<String> = rows.into_iter().flatten().collect();
+
+        let mut recommendation = KeyRecommendation::from_queries(&self.schema()?, &queries);
+        (
+            recommendation.current_order_by,
+            recommendation.current_partition_by,
+        ) = self.keys()?;
+        if !recommendation.current_partition_by.is_empty() {
+            recommendation.partition_by = None;
+        }
+
+        Ok(recommendation)
+    }
+}
+
+/// Identifiers inside `WHERE` and `PREWHERE` clauses, with whether each is compared by `=` or
+/// `IN`.
+fn filter_columns(query: &str) -> Vec<(&str, bool)> {
+    let tokens = tokenize(query);
+    let mut columns = Vec::new();
+    let mut in_filter = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_word("WHERE") || token.is_word("PREWHERE") {
+            in_filter = true;
+            continue;
+        }
+        if FILTER_END.iter().any(|keyword| token.is_word(keyword)) {
+            in_filter = false;
+            continue;
+        }
+        let Some(name) = token.name() else {
+            continue;
+        };
+        if !in_filter || tokens.get(i + 1) == Some(&Token::Punct(b'(')) {
+            continue;
+        }
+
+        let equality = match tokens.get(i + 1) {
+            Some(Token::Punct(b'=')) => true,
+            Some(next) => next.is_word("IN"),
+            None => false,
+        };
+        // `table.column` counts as `column`
+        let name = match tokens.get(i + 1) {
+            Some(Token::Punct(b'.')) => continue,
+            _ => name,
+        };
+        columns.push((name, equality));
+    }
+
+    columns
+}
@@ -5,7 +5,7 @@ use crate::error::Error;
 use crate::format::OutputFormat;
 use crate::log_level::LogLevel;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Arg<'a> {
     /// --config-file=<value>
     ConfigFilePath(Cow<'a, str>),
@@ -31,6 +31,18 @@ pub enum Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
+    pub fn into_owned(self) -> Arg<'static> {
+        match self {
+            Self::ConfigFilePath(v) => Arg::ConfigFilePath(v.into_owned().into()),
+            Self::LogLevel(v) => Arg::LogLevel(v),
+            Self::OutputFormat(v) => Arg::OutputFormat(v),
+            Self::MultiQuery => Arg::MultiQuery,
+            Self::Custom(k, v) => {
+                Arg::Custom(k.into_owned().into(), v.map(|v| v.into_owned().into()))
+            }
+        }
+    }
+
     pub(crate) fn to_cstring(&self) -> Result<CString, Error> {
         Ok(match self {
             Self::ConfigFilePath(v) => CString::new(format!("--config-file={}", v)),
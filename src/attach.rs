@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use crate::error::Error;
+use crate::format::InputFormat;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::table::Table;
+
+impl Session {
+    /// Makes files outside the data path queryable as `alias`, without copying them.
+    ///
+    /// `alias` is a view over `file(path, format)`, so reads always see the current files and
+    /// writes are rejected. `path` may contain globs (`*`, `?`, `{a,b}`, `**`); a directory
+    /// matches every file directly inside it.
+    pub fn attach_path(
+        &self,
+        alias: &str,
+        path: impl AsRef<Path>,
+        format: InputFormat,
+    ) -> Result<Table<'_>, Error> {
+        let path = path.as_ref();
+        let mut pattern = path.to_str().ok_or(Error::PathError)?.to_string();
+        if path.is_dir() {
+            pattern = format!("{}/*", pattern.trim_end_matches('/'));
+        }
+
+        self.execute(
+            &format!(
+                "CREATE OR REPLACE VIEW {} AS SELECT * FROM file({}, {})",
+                quote_qualified(alias),
+                quote_string(&pattern),
+                quote_string(format.as_str())
+            ),
+            None,
+        )?;
+
+        Ok(self.table(alias))
+    }
+
+    /// Removes a view created by `attach_path`, the files are left alone.
+    pub fn detach_path(&self, alias: &str) -> Result<(), Error> {
+        self.execute(
+            &format!("DROP VIEW IF EXISTS {}", quote_qualified(alias)),
+            None,
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::format::OutputFormat;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::tsv;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Where `SessionBuilder::with_audit` records executed statements.
+#[derive(Debug, Clone)]
+pub enum AuditSink {
+    /// Keeps the latest `capacity` entries in memory.
+    Memory { capacity: usize },
+    /// Appends TabSeparated lines to a file.
+    File(PathBuf),
+    /// Inserts into a MergeTree table of the session, created if missing.
+    Table(String),
+}
+
+/// Executed statement as recorded by the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// `log_comment` of the query, see `ExecuteOptions::comment`.
+    pub caller: String,
+    pub query: String,
+    /// FNV-1a hash of the query text.
+    pub query_hash: u64,
+    pub started_at: SystemTime,
+    pub elapsed: Duration,
+    pub rows_read: u64,
+    pub success: bool,
+    /// Empty for successful queries.
+    pub error: String,
+}
+
+pub(crate) struct AuditLog {
+    sink: AuditSink,
+    entries: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl AuditEntry {
+    pub(crate) fn new(query: &str, query_args: Option<&[Arg]>, started_at: SystemTime) -> Self {
+        let caller = query_args
+            .unwrap_or_default()
+            .iter()
+            .find_map(|arg| match arg {
+                Arg::Custom(name, Some(value)) if name == "log_comment" => Some(value.to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        Self {
+            caller,
+            query: query.to_string(),
+            query_hash: fnv1a(query.as_bytes()),
+            started_at,
+            elapsed: Duration::ZERO,
+            rows_read: 0,
+            success: false,
+            error: String::new(),
+        }
+    }
+
+    fn to_row(&self) -> [String; 8] {
+        [
+            micros(self.started_at).to_string(),
+            self.caller.clone(),
+            self.query_hash.to_string(),
+            self.elapsed.as_micros().to_string(),
+            self.rows_read.to_string(),
+            u8::from(self.success).to_string(),
+            self.error.clone(),
+            self.query.clone(),
+        ]
+    }
+
+    fn from_row(row: Vec<String>) -> Result<Self, Error> {
+        match <[String; 8]>::try_from(row) {
+            Ok([started_at, caller, query_hash, elapsed, rows_read, success, error, query]) => {
+                Ok(Self {
+                    caller,
+                    query,
+                    query_hash: parse_u64(&query_hash)?,
+                    started_at: UNIX_EPOCH + Duration::from_micros(parse_u64(&started_at)?),
+                    elapsed: Duration::from_micros(parse_u64(&elapsed)?),
+                    rows_read: parse_u64(&rows_read)?,
+                    success: success == "1",
+                    error,
+                })
+            }
+            Err(row) => Err(unexpected_columns(8, row.len())),
+        }
+    }
+}
+
+impl AuditLog {
+    pub(crate) fn new(sink: AuditSink) -> Self {
+        Self {
+            sink,
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Creates the audit table for `AuditSink::Table`.
+    pub(crate) fn init(&self, session: &Session) -> Result<(), Error> {
+        if let AuditSink::Table(table) = &self.sink {
+            session.execute_raw(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (event_time DateTime64(6), caller String, \
+                     query_hash UInt64, elapsed_us UInt64, rows_read UInt64, success UInt8, \
+                     error String, query String) ENGINE = MergeTree ORDER BY event_time",
+                    quote_qualified(table)
+                ),
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record(&self, session: &Session, entry: AuditEntry) -> Result<(), Error> {
+        match &self.sink {
+            AuditSink::Memory { capacity } => {
+                let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+                while entries.len() >= (*capacity).max(1) {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+            AuditSink::File(path) => {
+                let fields: Vec<String> = entry.to_row().iter().map(|f| tsv::escape(f)).collect();
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(format!("{}\n", fields.join("\t")).as_bytes())?;
+            }
+            AuditSink::Table(table) => {
+                let row = entry.to_row();
+                let values: Vec<String> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| match i {
+                        0 => format!("fromUnixTimestamp64Micro({})", field),
+                        1 | 6 | 7 => quote_string(field),
+                        _ => field.clone(),
+                    })
+                    .collect();
+                session.execute_raw(
+                    &format!(
+                        "INSERT INTO {} VALUES ({})",
+                        quote_qualified(table),
+                        values.join(", ")
+                    ),
+                    None,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn entries(&self, session: &Session) -> Result<Vec<AuditEntry>, Error> {
+        let rows = match &self.sink {
+            AuditSink::Memory { .. } => {
+                let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+                return Ok(entries.iter().cloned().collect());
+            }
+            AuditSink::File(path) => match std::fs::read(path) {
+                Ok(data) => tsv::parse(&data)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e.into()),
+            },
+            AuditSink::Table(table) => {
+                let result = session.execute_raw(
+                    &format!(
+                        "SELECT toUnixTimestamp64Micro(event_time), caller, query_hash, \
+                         elapsed_us, rows_read, success, error, query FROM {} ORDER BY event_time",
+                        quote_qualified(table)
+                    ),
+                    Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+                )?;
+                tsv::parse(result.data_ref())?
+            }
+        };
+
+        rows.into_iter().map(AuditEntry::from_row).collect()
+    }
+}
+
+fn micros(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros())
+        .unwrap_or(0)
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,24 @@
+//! Writes the schema snapshot of an existing session data path.
+//!
+//! Usage: `cargo run --features serde --bin chdb-schema-snapshot -- <data-path> <output.json>`
+
+use chdb_rust::session::SessionBuilder;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [data_path, output] = args.as_slice() else {
+        eprintln!("usage: chdb-schema-snapshot <data-path> <output.json>");
+        std::process::exit(2);
+    };
+
+    let result = SessionBuilder::new()
+        .with_data_path(data_path)
+        .open_existing()
+        .build()
+        .and_then(|session| session.export_schema_snapshot(output));
+
+    if let Err(e) = result {
+        eprintln!("chdb-schema-snapshot: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,106 @@
+//! Runs a session on a dedicated worker thread.
+//!
+//! Queries are sent to the worker over a channel, so callers never block inside the engine
+//! themselves. With the `async` feature `Handle::execute_async` returns a runtime-agnostic
+//! future, usable from tokio, async-std or smol alike.
+
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+
+/// Result as sent back by the worker.
+type Reply = Result<QueryResult, Error>;
+
+struct Job {
+    query: String,
+    args: Vec<Arg<'static>>,
+    reply: Box<dyn FnOnce(Reply) + Send>,
+}
+
+pub struct Handle {
+    sender: Option<mpsc::Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Handle {
+    /// Moves `session` to a new worker thread. Queries run one at a time, in order.
+    pub fn new(session: Session) -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+
+        let worker = std::thread::Builder::new()
+            .name("chdb-worker".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    (job.reply)(session.execute(&job.query, Some(&job.args)));
+                }
+            })?;
+
+        Ok(Self {
+            sender: Some(sender),
+            worker: Some(worker),
+        })
+    }
+
+    /// Runs `query` on the worker and waits for the result.
+    pub fn execute(
+        &self,
+        query: impl Into<String>,
+        args: Vec<Arg<'static>>,
+    ) -> Result<QueryResult, Error> {
+        let (sender, receiver) = mpsc::channel();
+        self.submit(query.into(), args, move |reply| {
+            let _ = sender.send(reply);
+        })?;
+
+        receiver.recv().map_err(|_| Error::WorkerGone)?
+    }
+
+    /// Runs `query` on the worker, resolving once it finishes.
+    #[cfg(feature = "async")]
+    pub fn execute_async(
+        &self,
+        query: impl Into<String>,
+        args: Vec<Arg<'static>>,
+    ) -> impl std::future::Future<Output = Result<QueryResult, Error>> {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        let submitted = self.submit(query.into(), args, move |reply| {
+            let _ = sender.send(reply);
+        });
+
+        async move {
+            submitted?;
+            receiver.await.map_err(|_| Error::WorkerGone)?
+        }
+    }
+
+    fn submit(
+        &self,
+        query: String,
+        args: Vec<Arg<'static>>,
+        reply: impl FnOnce(Reply) + Send + 'static,
+    ) -> Result<(), Error> {
+        self.sender
+            .as_ref()
+            .ok_or(Error::WorkerGone)?
+            .send(Job {
+                query,
+                args,
+                reply: Box::new(reply),
+            })
+            .map_err(|_| Error::WorkerGone)
+    }
+}
+
+impl Drop for Handle {
+    /// Waits for queued queries to finish and drops the session on the worker.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+use crate::table::Table;
+use crate::tsv::parse_u64;
+
+/// Local MergeTree copy of a remote source, see `Session::cache_remote`.
+pub struct RemoteCache<'a> {
+    table: Table<'a>,
+    source: String,
+    refresh: Duration,
+    refreshed_at: Mutex<Option<Instant>>,
+}
+
+impl Session {
+    /// Materializes `SELECT * FROM <remote_expr>` into table `name`, e.g. with
+    /// `remote_expr` "remote('ch-central:9000', db.products)" or a `url()`/`s3()` call.
+    ///
+    /// The copy is reloaded when older than `refresh`, including copies left by an earlier
+    /// process.
+    pub fn cache_remote(
+        &self,
+        name: &str,
+        remote_expr: &str,
+        refresh: Duration,
+    ) -> Result<RemoteCache<'_>, Error> {
+        let cache = RemoteCache {
+            table: self.table(name),
+            source: remote_expr.to_string(),
+            refresh,
+            refreshed_at: Mutex::new(None),
+        };
+
+        match cache.stored_age()? {
+            Some(age) if age < refresh => {
+                let refreshed_at = Instant::now().checked_sub(age);
+                *cache.refreshed_at.lock().unwrap_or_else(|e| e.into_inner()) = refreshed_at;
+            }
+            _ => cache.refresh()?,
+        }
+
+        Ok(cache)
+    }
+}
+
+impl<'a> RemoteCache<'a> {
+    pub fn table(&self) -> &Table<'a> {
+        &self.table
+    }
+
+    /// Reloads the copy from the remote source, replacing it atomically.
+    pub fn refresh(&self) -> Result<(), Error> {
+        self.table.session().execute(
+            &format!(
+                "CREATE OR REPLACE TABLE {} ENGINE = MergeTree ORDER BY tuple() AS SELECT * FROM {}",
+                self.table.quoted_name(),
+                self.source
+            ),
+            None,
+        )?;
+
+        *self.refreshed_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Refreshes the copy if it is older than the refresh interval, returns whether it did.
+    pub fn refresh_if_stale(&self) -> Result<bool, Error> {
+        let refreshed_at = *self.refreshed_at.lock().unwrap_or_else(|e| e.into_inner());
+        if refreshed_at.is_some_and(|at| at.elapsed() < self.refresh) {
+            return Ok(false);
+        }
+
+        self.refresh()?;
+        Ok(true)
+    }
+
+    /// Refreshes a stale copy and runs `query` locally.
+    ///
+    /// When the remote source can't be reached the query runs on the stale copy and the
+    /// error goes to `SessionBuilder::on_error`.
+    pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        if let Err(e) = self.refresh_if_stale() {
+            self.table.session().report_error(&e);
+        }
+
+        self.table.session().execute(query, query_args)
+    }
+
+    /// Time since the table was last (re)created, `None` if it doesn't exist.
+    fn stored_age(&self) -> Result<Option<Duration>, Error> {
+        let rows = self.table.session().query_rows(&format!(
+            "SELECT toUInt64(greatest(0, now() - metadata_modification_time)) FROM system.tables WHERE {}",
+            self.table.system_filter()
+        ))?;
+
+        match rows.into_iter().flatten().next() {
+            Some(seconds) => Ok(Some(Duration::from_secs(parse_u64(&seconds)?))),
+            None => Ok(None),
+        }
+    }
+}
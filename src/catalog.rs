@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::execute;
+use crate::format::InputFormat;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+use crate::sql::cte_names;
+use crate::sql::quote_identifier;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::sql::skip_block_comment;
+use crate::sql::skip_line;
+use crate::sql::skip_quoted;
+use crate::sql::token_span;
+use crate::sql::tokenize;
+use crate::sql::Token;
+use crate::sql::CLAUSE_KEYWORDS;
+use crate::table::Table;
+
+/// Named datasets over local files, queried like tables.
+///
+/// `rewrite` replaces dataset names following `FROM` or `JOIN` with `file()` table functions,
+/// so the files are read as they are at query time, nothing is copied into the session.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    datasets: BTreeMap<String, Dataset>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    /// Path or glob, e.g. "data/events/*.parquet", resolved as by `file()`.
+    pub pattern: String,
+    pub format: InputFormat,
+}
+
+/// File read by `query_over`.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Parquet(PathBuf),
+    /// CSV with a header row.
+    Csv(PathBuf),
+    /// One JSON object per line.
+    Json(PathBuf),
+    File(PathBuf, InputFormat),
+}
+
+impl Source {
+    pub fn table_function(&self) -> Result<String, Error> {
+        let (path, format) = match self {
+            Self::Parquet(path) => (path, InputFormat::Parquet),
+            Self::Csv(path) => (path, InputFormat::CSVWithNames),
+            Self::Json(path) => (path, InputFormat::JSONEachRow),
+            Self::File(path, format) => (path, *format),
+        };
+        let path = path.to_str().ok_or(Error::PathError)?;
+
+        Ok(format!(
+            "file({}, {})",
+            quote_string(path),
+            quote_string(format.as_str())
+        ))
+    }
+}
+
+impl Dataset {
+    pub fn table_function(&self) -> String {
+        format!(
+            "file({}, {})",
+            quote_string(&self.pattern),
+            quote_string(self.format.as_str())
+        )
+    }
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `name`, replacing a dataset of the same name.
+    pub fn add(&mut self, name: &str, pattern: &str, format: InputFormat) -> &mut Self {
+        self.datasets.insert(
+            name.to_string(),
+            Dataset {
+                pattern: pattern.to_string(),
+                format,
+            },
+        );
+        self
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Dataset> {
+        self.datasets.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Dataset> {
+        self.datasets.get(name)
+    }
+
+    /// Replaces references to datasets in `sql`, aliasing them by their name so qualified
+    /// columns (`events.ts`) keep working. Names shadowed by a CTE and `db.name` references
+    /// are left alone.
+    pub fn rewrite(&self, sql: &str) -> String {
+        let tokens = tokenize(sql);
+        let ctes = cte_names(&tokens);
+        let mut replacements = Vec::new();
+
+        for (i, token) in tokens.iter().enumerate().skip(1) {
+            let Some(name) = token.name() else {
+                continue;
+            };
+            let Some(dataset) = self.datasets.get(name) else {
+                continue;
+            };
+            let is_table = tokens[i - 1].is_word("FROM") || tokens[i - 1].is_word("JOIN");
+            let next = tokens.get(i + 1);
+            let is_qualified = matches!(next, Some(Token::Punct(b'.' | b'(')));
+            if !is_table || is_qualified || ctes.contains(name) {
+                continue;
+            }
+
+            let has_alias = match next {
+                Some(Token::Word(word)) => {
+                    word.eq_ignore_ascii_case("AS")
+                        || !CLAUSE_KEYWORDS
+                            .iter()
+                            .any(|keyword| keyword.eq_ignore_ascii_case(word))
+                }
+                Some(Token::Quoted(_)) => true,
+                _ => false,
+            };
+            let mut replacement = dataset.table_function();
+            if !has_alias {
+                replacement.push_str(&format!(" AS {}", quote_identifier(name)));
+            }
+            if let Some(span) = token_span(sql, token) {
+                replacements.push((span, replacement));
+            }
+        }
+
+        let mut rewritten = sql.to_string();
+        for (span, replacement) in replacements.into_iter().rev() {
+            rewritten.replace_range(span, &replacement);
+        }
+        rewritten
+    }
+
+    /// Creates a view for every dataset, so tools that can't go through `rewrite` see them as
+    /// ordinary tables. Views are replaced if they exist.
+    pub fn materialize_views<'a>(&self, session: &'a Session) -> Result<Vec<Table<'a>>, Error> {
+        self.datasets
+            .iter()
+            .map(|(name, dataset)| {
+                session.execute(
+                    &format!(
+                        "CREATE OR REPLACE VIEW {} AS SELECT * FROM {}",
+                        quote_qualified(name),
+                        dataset.table_function()
+                    ),
+                    None,
+                )?;
+                Ok(session.table(name))
+            })
+            .collect()
+    }
+
+    /// Rewrites `query` and runs it on `session`.
+    pub fn execute(
+        &self,
+        session: &Session,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        session.execute(&self.rewrite(query), query_args)
+    }
+}
+
+/// Runs `sql` over files without a session, `{0}`, `{1}`... standing for `sources` in order:
+///
+/// ```text
+/// SELECT o.id, c.name FROM {0} AS o JOIN {1} AS c ON o.customer_id = c.id
+/// ```
+///
+/// Placeholders inside string literals, quoted identifiers and comments are left alone.
+pub fn query_over(
+    sources: &[Source],
+    sql: &str,
+    query_args: Option<&[Arg]>,
+) -> Result<QueryResult, Error> {
+    let query = substitute_sources(sql, |placeholder| {
+        let Ok(index) = placeholder.parse::<usize>() else {
+            return Ok(None);
+        };
+        sources
+            .get(index)
+            .map(Some)
+            .ok_or_else(|| Error::TemplateError(format!("no source for placeholder {{{}}}", index)))
+    })?;
+
+    execute(&query, query_args)
+}
+
+/// Like `query_over`, with each source bound to a name used as its placeholder:
+///
+/// ```text
+/// SELECT o.id, c.name FROM {orders} AS o JOIN {customers} AS c ON o.customer_id = c.id
+/// ```
+pub fn query_over_named(
+    sources: &[(&str, Source)],
+    sql: &str,
+    query_args: Option<&[Arg]>,
+) -> Result<QueryResult, Error> {
+    let query = substitute_sources(sql, |placeholder| {
+        sources
+            .iter()
+            .find(|(name, _)| *name == placeholder)
+            .map(|(_, source)| Some(source))
+            .ok_or_else(|| Error::TemplateError(format!("no source named {{{}}}", placeholder)))
+    })?;
+
+    execute(&query, query_args)
+}
+
+/// Replaces `{placeholder}`s outside literals and comments with the table function of the
+/// source `lookup` returns for them, `Ok(None)` leaves the placeholder as it is.
+fn substitute_sources<'s>(
+    sql: &str,
+    lookup: impl Fn(&str) -> Result<Option<&'s Source>, Error>,
+) -> Result<String, Error> {
+    let bytes = sql.as_bytes();
+    let mut query = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => i = skip_quoted(bytes, i),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i),
+            b'#' => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+            b'{' => {
+                let len = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                    .count();
+                let end = i + 1 + len;
+                if len == 0 || bytes.get(end) != Some(&b'}') {
+                    i += 1;
+                    continue;
+                }
+
+                let Some(source) = lookup(&sql[i + 1..end])? else {
+                    i = end + 1;
+                    continue;
+                };
+
+                query.push_str(&sql[copied..i]);
+                query.push_str(&source.table_function()?);
+                i = end + 1;
+                copied = i;
+            }
+            _ => i += 1,
+        }
+    }
+    query.push_str(&sql[copied..]);
+
+    Ok(query)
+}
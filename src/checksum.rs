@@ -0,0 +1,34 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Order independent summary of a table's contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableChecksum {
+    pub rows: u64,
+    /// Wrapping sum of `cityHash64` over every row.
+    pub hash: u64,
+}
+
+impl Session {
+    /// Checksums `table`, equal for tables holding the same rows in any order or part layout.
+    ///
+    /// Column types matter: the same values stored as different types hash differently.
+    pub fn checksum_table(&self, table: &str) -> Result<TableChecksum, Error> {
+        let rows = self.query_rows(&format!(
+            "SELECT count(), sum(cityHash64(*)) FROM {}",
+            quote_qualified(table)
+        ))?;
+
+        match rows.into_iter().next().map(<[String; 2]>::try_from) {
+            Some(Ok([rows, hash])) => Ok(TableChecksum {
+                rows: parse_u64(&rows)?,
+                hash: parse_u64(&hash)?,
+            }),
+            Some(Err(row)) => Err(unexpected_columns(2, row.len())),
+            None => Err(Error::InvalidData("empty checksum result".to_string())),
+        }
+    }
+}
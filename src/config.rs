@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::error::Error;
+
+/// Server-level engine configuration that can't be set per query, written to an XML file
+/// passed with `--config-file`, see `SessionBuilder::with_config`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineConfig {
+    entries: BTreeMap<Vec<String>, String>,
+}
+
+impl EngineConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a configuration key, nested elements are separated by dots, e.g.
+    /// "merge_tree.parts_to_throw_insert".
+    pub fn with_value(mut self, key: &str, value: impl ToString) -> Self {
+        let path = key.split('.').map(str::to_string).collect();
+        self.entries.insert(path, value.to_string());
+        self
+    }
+
+    /// Directory for temporary data, e.g. sorts and aggregations spilled to disk.
+    pub fn with_tmp_path(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_str().ok_or(Error::PathError)?;
+        // the engine expects a trailing slash
+        let path = if path.ends_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/", path)
+        };
+        Ok(self.with_value("tmp_path", path))
+    }
+
+    /// Threads for background merges and mutations.
+    pub fn with_background_pool_size(self, threads: u64) -> Self {
+        self.with_value("background_pool_size", threads)
+    }
+
+    /// Caps the combined read rate of all merges, 0 means unlimited.
+    pub fn with_max_merges_bandwidth(self, bytes_per_second: u64) -> Self {
+        self.with_value("max_merges_bandwidth_for_server", bytes_per_second)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the entries of `other`, replacing keys set in both.
+    pub(crate) fn merge(&mut self, other: EngineConfig) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<clickhouse>\n");
+        let mut open: Vec<&str> = Vec::new();
+
+        for (path, value) in &self.entries {
+            let (name, parents) = path.split_last().expect("config keys are never empty");
+            let common = open
+                .iter()
+                .zip(parents)
+                .take_while(|(a, b)| **a == b.as_str())
+                .count();
+            while open.len() > common {
+                let element = open.pop().unwrap_or_default();
+                let _ = writeln!(xml, "{}</{}>", indent(open.len()), element);
+            }
+            for parent in &parents[common..] {
+                let _ = writeln!(xml, "{}<{}>", indent(open.len()), parent);
+                open.push(parent);
+            }
+            let _ = writeln!(
+                xml,
+                "{}<{name}>{}</{name}>",
+                indent(open.len()),
+                escape(value)
+            );
+        }
+        while let Some(element) = open.pop() {
+            let _ = writeln!(xml, "{}</{}>", indent(open.len()), element);
+        }
+
+        xml.push_str("</clickhouse>\n");
+        xml
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth + 1)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
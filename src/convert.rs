@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::execute;
+use crate::format::InputFormat;
+use crate::format::OutputFormat;
+use crate::sql::quote_string;
+
+/// Options for `convert`.
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    structure: Option<String>,
+    columns: String,
+    overwrite: bool,
+    compression: Option<String>,
+    args: Vec<Arg<'static>>,
+}
+
+impl ConvertOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Input structure, e.g. "id UInt64, name String". Inferred from the input by default.
+    pub fn with_structure(mut self, structure: &str) -> Self {
+        self.structure = Some(structure.to_string());
+        self
+    }
+
+    /// Select list written to the output, "*" by default.
+    pub fn with_columns(mut self, columns: &str) -> Self {
+        self.columns = columns.to_string();
+        self
+    }
+
+    /// Replaces an existing output file. Enabled by default, otherwise converting into an
+    /// existing file fails.
+    pub fn with_overwrite(mut self, value: bool) -> Self {
+        self.overwrite = value;
+        self
+    }
+
+    /// Compresses the output, e.g. "gzip" or "zstd". By default compression follows the
+    /// output file extension.
+    pub fn with_compression(mut self, method: &str) -> Self {
+        self.compression = Some(method.to_string());
+        self
+    }
+
+    /// Format settings, e.g. `CsvOptions::to_args`.
+    pub fn with_args(mut self, args: impl IntoIterator<Item = Arg<'static>>) -> Self {
+        self.args.extend(args);
+        self
+    }
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            structure: None,
+            columns: "*".to_string(),
+            overwrite: true,
+            compression: None,
+            args: Vec::new(),
+        }
+    }
+}
+
+/// Converts a file between formats, e.g. CSV to Parquet, without a session.
+///
+/// `input` may contain globs to convert several files into one output.
+pub fn convert(
+    input: impl AsRef<Path>,
+    input_format: InputFormat,
+    output: impl AsRef<Path>,
+    output_format: OutputFormat,
+    options: &ConvertOptions,
+) -> Result<(), Error> {
+    let input = input.as_ref().to_str().ok_or(Error::PathError)?;
+    let output = output.as_ref().to_str().ok_or(Error::PathError)?;
+
+    let mut source = format!(
+        "file({}, {}",
+        quote_string(input),
+        quote_string(input_format.as_str())
+    );
+    if let Some(structure) = &options.structure {
+        source.push_str(&format!(", {}", quote_string(structure)));
+    }
+    source.push(')');
+
+    let mut query = format!(
+        "SELECT {} FROM {} INTO OUTFILE {}",
+        options.columns,
+        source,
+        quote_string(output)
+    );
+    if options.overwrite {
+        query.push_str(" TRUNCATE");
+    }
+    if let Some(method) = &options.compression {
+        query.push_str(&format!(" COMPRESSION {}", quote_string(method)));
+    }
+    query.push_str(&format!(" FORMAT {}", output_format.as_str()));
+
+    execute(&query, Some(&options.args))?;
+    Ok(())
+}
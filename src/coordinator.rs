@@ -0,0 +1,76 @@
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+use crate::settings::setting;
+
+/// Shares one data path between a single writer and any number of readers.
+///
+/// Readers run concurrently with each other and never alongside a writer, so they always see
+/// the result of complete write batches. Reader queries run with `readonly = 2`, rejecting
+/// any statement that modifies data.
+pub struct Coordinator {
+    session: Session,
+    lock: RwLock<()>,
+}
+
+pub struct ReadHandle<'a> {
+    session: &'a Session,
+    _guard: RwLockReadGuard<'a, ()>,
+}
+
+pub struct WriteHandle<'a> {
+    session: &'a Session,
+    _guard: RwLockWriteGuard<'a, ()>,
+}
+
+impl Coordinator {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            lock: RwLock::new(()),
+        }
+    }
+
+    /// Blocks while a write handle is alive.
+    pub fn read(&self) -> ReadHandle<'_> {
+        ReadHandle {
+            session: &self.session,
+            _guard: self.lock.read().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// Blocks until all other handles are dropped.
+    pub fn write(&self) -> WriteHandle<'_> {
+        WriteHandle {
+            session: &self.session,
+            _guard: self.lock.write().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    pub fn into_session(self) -> Session {
+        self.session
+    }
+}
+
+impl ReadHandle<'_> {
+    pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        let mut args = query_args.map_or_else(Vec::new, <[Arg]>::to_vec);
+        args.push(setting("readonly", 2));
+        self.session.execute(query, Some(&args))
+    }
+}
+
+impl WriteHandle<'_> {
+    pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        self.session.execute(query, query_args)
+    }
+
+    pub fn session(&self) -> &Session {
+        self.session
+    }
+}
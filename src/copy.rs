@@ -0,0 +1,113 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::settings::setting;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::temp::TempFile;
+
+/// Options for `copy`.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    create_table: bool,
+    row_group_size: u64,
+    filter: Option<String>,
+}
+
+impl CopyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the destination table (IF NOT EXISTS) from the source schema, sorting and
+    /// partition keys. Enabled by default.
+    pub fn with_create_table(mut self, value: bool) -> Self {
+        self.create_table = value;
+        self
+    }
+
+    /// Rows per Parquet row group, bounds memory used on both sides. 1M by default.
+    pub fn with_chunk_rows(mut self, rows: u64) -> Self {
+        self.row_group_size = rows;
+        self
+    }
+
+    /// Only copies rows matching a raw SQL condition.
+    pub fn with_where(mut self, condition: &str) -> Self {
+        self.filter = Some(condition.to_string());
+        self
+    }
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            create_table: true,
+            row_group_size: 1_000_000,
+            filter: None,
+        }
+    }
+}
+
+/// Copies `src_table` of `src` into `dst_table` of `dst` through a temporary Parquet file.
+///
+/// The destination is created as a MergeTree table when it doesn't exist yet.
+pub fn copy(
+    src: &Session,
+    src_table: &str,
+    dst: &Session,
+    dst_table: &str,
+    options: &CopyOptions,
+) -> Result<(), Error> {
+    if options.create_table {
+        create_destination(src, src_table, dst, dst_table)?;
+    }
+
+    let file = TempFile::new("parquet");
+    let path = quote_string(file.path_str()?);
+
+    let mut export = format!("SELECT * FROM {}", quote_qualified(src_table));
+    if let Some(filter) = &options.filter {
+        export.push_str(&format!(" WHERE {}", filter));
+    }
+    export.push_str(&format!(" INTO OUTFILE {} FORMAT Parquet", path));
+    src.execute(
+        &export,
+        Some(&[setting(
+            "output_format_parquet_row_group_size",
+            options.row_group_size,
+        )]),
+    )?;
+
+    dst.execute(
+        &format!(
+            "INSERT INTO {} SELECT * FROM file({}, 'Parquet')",
+            quote_qualified(dst_table),
+            path
+        ),
+        None,
+    )?;
+
+    Ok(())
+}
+
+fn create_destination(
+    src: &Session,
+    src_table: &str,
+    dst: &Session,
+    dst_table: &str,
+) -> Result<(), Error> {
+    let table = src.table(src_table);
+    let schema = table.schema()?;
+    let (sorting_key, partition_key) = table.keys()?;
+
+    let mut builder = schema.to_table_builder(dst_table).with_if_not_exists(true);
+    if !sorting_key.is_empty() {
+        builder = builder.with_order_by(&format!("({})", sorting_key));
+    }
+    if !partition_key.is_empty() {
+        builder = builder.with_partition_by(&partition_key);
+    }
+    builder.build(dst)?;
+
+    Ok(())
+}
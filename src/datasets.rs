@@ -0,0 +1,37 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::execute;
+use crate::format::OutputFormat;
+use crate::query::unix_secs;
+use crate::query_result::QueryResult;
+
+/// Table fragment with a single `number` column holding `0..n`.
+pub fn numbers(n: u64) -> String {
+    format!("numbers({})", n)
+}
+
+/// Table fragment with a single `ts` DateTime column covering `[start, end)` every `step`.
+///
+/// Sub-second precision is dropped.
+pub fn time_series(start: SystemTime, end: SystemTime, step: Duration) -> String {
+    let start = unix_secs(start);
+    let end = unix_secs(end);
+    let step = step.as_secs().max(1);
+    let count = end.saturating_sub(start).div_ceil(step);
+
+    format!(
+        "(SELECT toDateTime({} + number * {}, 'UTC') AS ts FROM numbers({}))",
+        start, step, count
+    )
+}
+
+/// Runs `SELECT * FROM <fragment>` with the stateless engine.
+pub fn fetch(fragment: &str, format: OutputFormat) -> Result<QueryResult, Error> {
+    execute(
+        &format!("SELECT * FROM {}", fragment),
+        Some(&[Arg::OutputFormat(format)]),
+    )
+}
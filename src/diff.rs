@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::tsv;
+
+pub type Row = Vec<Option<String>>;
+
+/// Row level differences between two results, ordered by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultDiff {
+    pub columns: Vec<String>,
+    /// Rows only present in the second result.
+    pub added: Vec<Row>,
+    /// Rows only present in the first result.
+    pub removed: Vec<Row>,
+    /// Rows present in both with different values, as `(first, second)`.
+    pub changed: Vec<(Row, Row)>,
+}
+
+impl ResultDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two `TabSeparatedWithNames` results, matching rows on the `keys` columns.
+///
+/// Both results must have the same columns and keys must be unique within each result.
+pub fn diff(a: &QueryResult, b: &QueryResult, keys: &[&str]) -> Result<ResultDiff, Error> {
+    let (columns, a) = parse(a)?;
+    let (b_columns, b) = parse(b)?;
+    if columns != b_columns {
+        return Err(Error::InvalidData(format!(
+            "results have different columns: {:?} and {:?}",
+            columns, b_columns
+        )));
+    }
+
+    let key_indexes = keys
+        .iter()
+        .map(|key| {
+            columns
+                .iter()
+                .position(|c| c == key)
+                .ok_or_else(|| Error::InvalidData(format!("no key column {}", key)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut a = by_key(a, &key_indexes)?;
+    let b = by_key(b, &key_indexes)?;
+
+    let mut diff = ResultDiff {
+        columns,
+        ..ResultDiff::default()
+    };
+    for (key, row) in b {
+        match a.remove(&key) {
+            Some(old) if old != row => diff.changed.push((old, row)),
+            Some(_) => {}
+            None => diff.added.push(row),
+        }
+    }
+    diff.removed = a.into_values().collect();
+
+    Ok(diff)
+}
+
+fn parse(result: &QueryResult) -> Result<(Vec<String>, Vec<Row>), Error> {
+    let mut rows = tsv::parse_nullable(result.data_ref(), b"\\N")?.into_iter();
+    let columns = rows
+        .next()
+        .ok_or_else(|| Error::InvalidData("expected a TabSeparatedWithNames header".to_string()))?
+        .into_iter()
+        .map(Option::unwrap_or_default)
+        .collect();
+
+    Ok((columns, rows.collect()))
+}
+
+fn by_key(rows: Vec<Row>, key_indexes: &[usize]) -> Result<BTreeMap<Row, Row>, Error> {
+    let mut map = BTreeMap::new();
+    for row in rows {
+        let key: Row = key_indexes
+            .iter()
+            .map(|i| row.get(*i).cloned().flatten())
+            .collect();
+        if map.contains_key(&key) {
+            return Err(Error::InvalidData(format!("duplicate key {:?}", key)));
+        }
+        map.insert(key, row);
+    }
+    Ok(map)
+}
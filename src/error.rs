@@ -1,4 +1,7 @@
 use std::ffi::NulError;
+use std::fmt::Write;
+
+use crate::sql::split_statements;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -18,4 +21,123 @@ pub enum Error {
     NonUtf8Sequence(String),
     #[error("{0}")]
     QueryError(String),
+    #[error("Data path already contains a database: {0}")]
+    PathNotEmpty(String),
+    #[error("Data path does not contain a database: {0}")]
+    NoExistingData(String),
+    #[error("Disk quota exceeded: {used} of {limit} bytes used")]
+    DiskQuotaExceeded { used: u64, limit: u64 },
+    #[error("Invalid query template: {0}")]
+    TemplateError(String),
+    #[error("Refusing to delete directory not created by a session: {0}")]
+    RefusingToDeleteForeignDir(String),
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Statement not allowed: {0}")]
+    Forbidden(String),
+    #[error("Query of {size} bytes exceeds max_query_size of {limit}")]
+    QueryTooLarge { size: usize, limit: usize },
+    #[error("Query exceeded the memory soft limit of {limit} bytes: {message}")]
+    MemorySoftLimit { limit: u64, message: String },
+    #[error("{message} (did you mean {}?)", suggestions.join(", "))]
+    UnknownName {
+        message: String,
+        /// Existing names closest to the unknown one, closest first.
+        suggestions: Vec<String>,
+    },
+    #[error("The engine returned no result")]
+    NoResult,
+    #[error("Statement {statement_index} of script failed: {source}")]
+    ScriptFailed {
+        /// Zero-based position of the statement in the script.
+        statement_index: usize,
+        statement_sql: String,
+        #[source]
+        source: Box<Error>,
+    },
+    #[error("The chdb worker thread is gone, the query was not run to completion")]
+    WorkerGone,
+    #[error(
+        "system.query_log is not available, enable the query_log engine configuration \
+         section and the log_queries setting"
+    )]
+    QueryLogUnavailable,
+}
+
+impl Error {
+    /// Engine error code, parsed from the "Code: <n>." prefix of the engine's message.
+    pub fn code(&self) -> Option<u32> {
+        let message = match self {
+            Self::QueryError(message)
+            | Self::MemorySoftLimit { message, .. }
+            | Self::UnknownName { message, .. } => message,
+            Self::ScriptFailed { source, .. } => return source.code(),
+            _ => return None,
+        };
+        let rest = &message[message.find("Code: ")? + 6..];
+        let end = rest.find(|c: char| !c.is_ascii_digit())?;
+        rest[..end].parse().ok()
+    }
+
+    /// Multi-line rendering for terminals: the message, the line of `query` the engine
+    /// reported a syntax error at with a caret under the position, and a hint for common
+    /// error codes.
+    pub fn render_pretty(&self, query: &str) -> String {
+        let message = self.to_string();
+        let mut out = format!("error: {}\n", message);
+
+        if let Some(position) = syntax_error_position(&message) {
+            let mut position = position.min(query.len());
+            while !query.is_char_boundary(position) {
+                position -= 1;
+            }
+            let line_start = query[..position].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = query[position..]
+                .find('\n')
+                .map_or(query.len(), |i| position + i);
+            let line_number = query[..line_start].matches('\n').count() + 1;
+            let column = query[line_start..position].chars().count();
+
+            let gutter = " ".repeat(line_number.to_string().len());
+            let _ = writeln!(out, "{} |", gutter);
+            let _ = writeln!(out, "{} | {}", line_number, &query[line_start..line_end]);
+            let _ = writeln!(out, "{} | {}^", gutter, " ".repeat(column));
+        }
+
+        if let Some(hint) = self.hint(query) {
+            let _ = writeln!(out, "hint: {}", hint);
+        }
+
+        out
+    }
+
+    fn hint(&self, query: &str) -> Option<&'static str> {
+        Some(match self.code()? {
+            62 if split_statements(query).len() > 1 => {
+                "the query holds several statements, run it with `Session::execute_script` or \
+                 pass `Arg::MultiQuery`"
+            }
+            27 | 117 => "the data doesn't match the input format or structure",
+            46 => "unknown function, check the spelling and the engine version",
+            47 => "the column doesn't exist, check `Session::describe` for the available columns",
+            60 => "the table doesn't exist in this session's data path",
+            81 => "the database doesn't exist, create it first",
+            159 => "the query ran longer than `max_execution_time`",
+            241 => {
+                "reduce `max_threads` or let sorts and aggregations spill with \
+                    `SessionBuilder::with_spill_to_disk`"
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Byte offset of "failed at position <n>" (1-based) in a syntax error message.
+fn syntax_error_position(message: &str) -> Option<usize> {
+    let rest = &message[message.find("failed at position ")? + 19..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let position: usize = rest[..end].parse().ok()?;
+    Some(position.saturating_sub(1))
 }
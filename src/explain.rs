@@ -0,0 +1,132 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::tsv::parse_u64;
+
+/// Index analysis of one table read, see `Session::index_usage`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableIndexUsage {
+    /// "db.table" as shown by `ReadFromMergeTree`.
+    pub table: String,
+    /// Indexes in the order the engine applied them.
+    pub indexes: Vec<IndexUsage>,
+}
+
+/// Parts and granules left after applying one index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// "MinMax", "Partition", "PrimaryKey" or "Skip".
+    pub kind: String,
+    /// Name of a skip index.
+    pub name: Option<String>,
+    /// Key columns used, for the primary key the matched prefix.
+    pub keys: Vec<String>,
+    pub condition: Option<String>,
+    pub parts_selected: u64,
+    pub parts_total: u64,
+    pub granules_selected: u64,
+    pub granules_total: u64,
+}
+
+const INDEX_KINDS: &[&str] = &[
+    "MinMax",
+    "Partition",
+    "PrimaryKey",
+    "Skip",
+    "PrimaryKeyExpand",
+];
+
+impl TableIndexUsage {
+    /// Parses the text of `EXPLAIN indexes = 1`, one entry per `ReadFromMergeTree` step.
+    pub fn from_explain(plan: &str) -> Result<Vec<Self>, Error> {
+        let mut tables: Vec<Self> = Vec::new();
+        let mut in_keys = false;
+
+        for line in plan.lines() {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("ReadFromMergeTree (") {
+                tables.push(Self {
+                    table: rest.trim_end_matches(')').to_string(),
+                    indexes: Vec::new(),
+                });
+                in_keys = false;
+                continue;
+            }
+            let Some(table) = tables.last_mut() else {
+                continue;
+            };
+
+            if INDEX_KINDS.contains(&line) {
+                table.indexes.push(IndexUsage {
+                    kind: line.to_string(),
+                    ..IndexUsage::default()
+                });
+                in_keys = false;
+                continue;
+            }
+            let Some(index) = table.indexes.last_mut() else {
+                continue;
+            };
+
+            let field = line.split_once(": ");
+            match field {
+                Some(("Name", name)) => index.name = Some(name.to_string()),
+                Some(("Condition", condition)) => index.condition = Some(condition.to_string()),
+                Some(("Parts", parts)) => {
+                    (index.parts_selected, index.parts_total) = parse_fraction(parts)?
+                }
+                Some(("Granules", granules)) => {
+                    (index.granules_selected, index.granules_total) = parse_fraction(granules)?
+                }
+                _ if line == "Keys:" => {
+                    in_keys = true;
+                    continue;
+                }
+                None if in_keys && !line.ends_with(':') => {
+                    index.keys.push(line.to_string());
+                    continue;
+                }
+                _ => {}
+            }
+            in_keys = false;
+        }
+
+        Ok(tables)
+    }
+}
+
+impl Session {
+    /// Indexes used to read the tables of `query`, from `EXPLAIN indexes = 1`.
+    pub fn index_usage(&self, query: &str) -> Result<Vec<TableIndexUsage>, Error> {
+        let rows = self.query_rows(&format!("EXPLAIN indexes = 1 {}", query))?;
+        let plan = rows.into_iter().flatten().collect::<Vec<_>>().join("\n");
+
+        TableIndexUsage::from_explain(&plan)
+    }
+
+    /// Execution pipeline of `query` as a Graphviz DOT graph, from
+    /// `EXPLAIN PIPELINE graph = 1`. Render it with e.g. `dot -Tsvg`.
+    pub fn explain_graphviz(&self, query: &str) -> Result<String, Error> {
+        let rows = self.query_rows(&format!("EXPLAIN PIPELINE graph = 1 {}", query))?;
+
+        let mut dot = rows.into_iter().flatten().collect::<Vec<_>>().join("\n");
+        if !dot.trim_start().starts_with("digraph") {
+            return Err(Error::InvalidData(
+                "EXPLAIN PIPELINE returned no graph".to_string(),
+            ));
+        }
+        dot.push('\n');
+
+        Ok(dot)
+    }
+}
+
+/// Parses "selected/total".
+fn parse_fraction(value: &str) -> Result<(u64, u64), Error> {
+    match value.split_once('/') {
+        Some((selected, total)) => Ok((parse_u64(selected.trim())?, parse_u64(total.trim())?)),
+        None => Err(Error::InvalidData(format!(
+            "expected selected/total: {}",
+            value
+        ))),
+    }
+}
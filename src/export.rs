@@ -0,0 +1,179 @@
+use std::io::Write;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::format::OutputFormat;
+use crate::query_result::QueryStats;
+use crate::session::Session;
+use crate::settings::setting;
+use crate::settings::CsvOptions;
+use crate::sql::quote_qualified;
+use crate::sql::tokenize;
+use crate::sql::Token;
+
+/// Options of `Session::export_csv`.
+#[derive(Debug, Clone, Default)]
+pub struct CsvExportOptions {
+    dialect: CsvOptions,
+    validate: bool,
+    crlf: bool,
+}
+
+impl CsvExportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dialect(mut self, dialect: CsvOptions) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Checks the output against RFC 4180 before writing anything: quoting, escaped quotes
+    /// and the same number of fields in every record as in the header.
+    pub fn with_validation(mut self, value: bool) -> Self {
+        self.validate = value;
+        self
+    }
+
+    /// Ends records with CRLF as RFC 4180 requires, instead of LF.
+    pub fn with_crlf(mut self, value: bool) -> Self {
+        self.crlf = value;
+        self
+    }
+}
+
+impl Session {
+    /// Writes the output of `query` in `format` to `writer`, straight from the engine's buffer.
+    ///
+    /// The engine still materializes the whole output; for results that don't fit in memory
+    /// use `SELECT ... INTO OUTFILE` instead.
+    pub fn query_to_writer(
+        &self,
+        query: &str,
+        format: OutputFormat,
+        writer: &mut impl Write,
+    ) -> Result<QueryStats, Error> {
+        let result = self.execute(query, Some(&[Arg::OutputFormat(format)]))?;
+        writer.write_all(result.data_ref())?;
+        writer.flush()?;
+        Ok(result.stats())
+    }
+}
+
+impl Session {
+    /// Writes a table, or the result of a `SELECT`/`WITH` query, to `writer` as CSV with a
+    /// header row.
+    pub fn export_csv(
+        &self,
+        table_or_query: &str,
+        writer: &mut impl Write,
+        options: &CsvExportOptions,
+    ) -> Result<QueryStats, Error> {
+        let is_query = tokenize(table_or_query).first().is_some_and(|token| {
+            token.is_word("SELECT") || token.is_word("WITH") || *token == Token::Punct(b'(')
+        });
+        let query = if is_query {
+            table_or_query.to_string()
+        } else {
+            format!("SELECT * FROM {}", quote_qualified(table_or_query))
+        };
+
+        let mut args = options.dialect.to_args();
+        if options.crlf {
+            args.push(setting("output_format_csv_crlf_end_of_line", 1));
+        }
+        args.push(Arg::OutputFormat(OutputFormat::CSVWithNames));
+
+        let result = self.execute(&query, Some(&args))?;
+        if options.validate {
+            let delimiter = options.dialect.delimiter().unwrap_or(',');
+            validate_csv(result.data_ref(), delimiter as u8, options.crlf)?;
+        }
+
+        writer.write_all(result.data_ref())?;
+        writer.flush()?;
+        Ok(result.stats())
+    }
+}
+
+/// Checks `data` against RFC 4180, reporting the first violation with its 1-based record.
+pub fn validate_csv(data: &[u8], delimiter: u8, crlf: bool) -> Result<(), Error> {
+    let mut record = 1;
+    let mut fields = 1;
+    let mut header_fields = None;
+    let mut i = 0;
+
+    let error = |record: usize, message: &str| {
+        Err(Error::InvalidData(format!(
+            "CSV record {}: {}",
+            record, message
+        )))
+    };
+
+    while i < data.len() {
+        // one field per iteration, `i` at its first byte
+        if data[i] == b'"' {
+            i += 1;
+            loop {
+                match data.get(i) {
+                    None => return error(record, "unterminated quoted field"),
+                    Some(b'"') if data.get(i + 1) == Some(&b'"') => i += 2,
+                    Some(b'"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(_) => i += 1,
+                }
+            }
+        } else {
+            while i < data.len() && data[i] != delimiter && data[i] != b'\r' && data[i] != b'\n' {
+                if data[i] == b'"' {
+                    return error(record, "quote in an unquoted field");
+                }
+                i += 1;
+            }
+        }
+
+        match data.get(i) {
+            None => break,
+            Some(b) if *b == delimiter => {
+                fields += 1;
+                i += 1;
+            }
+            Some(b'\r') if data.get(i + 1) == Some(&b'\n') => {
+                end_record(&mut header_fields, fields, record)?;
+                (record, fields, i) = (record + 1, 1, i + 2);
+            }
+            Some(b'\n') if !crlf => {
+                end_record(&mut header_fields, fields, record)?;
+                (record, fields, i) = (record + 1, 1, i + 1);
+            }
+            Some(b'\n') => return error(record, "record ends with LF instead of CRLF"),
+            Some(_) => return error(record, "characters after a closing quote"),
+        }
+    }
+
+    if i > 0 && !matches!(data.last(), Some(b'\n')) {
+        end_record(&mut header_fields, fields, record)?;
+    }
+    Ok(())
+}
+
+fn end_record(
+    header_fields: &mut Option<usize>,
+    fields: usize,
+    record: usize,
+) -> Result<(), Error> {
+    match *header_fields {
+        None => *header_fields = Some(fields),
+        Some(expected) if expected != fields => {
+            return Err(Error::InvalidData(format!(
+                "CSV record {}: {} fields, the header has {}",
+                record, fields, expected
+            )))
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,683 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+use crate::sql::cte_scopes;
+use crate::sql::split_statements;
+use crate::sql::tokenize;
+use crate::sql::Token;
+use crate::sql::CLAUSE_KEYWORDS;
+
+/// Statement kinds, determined by the leading keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementKind {
+    /// `SELECT`, `WITH ... SELECT` and parenthesized selects.
+    Select,
+    Insert,
+    Create,
+    Alter,
+    Drop,
+    Truncate,
+    Rename,
+    Attach,
+    Detach,
+    Optimize,
+    Show,
+    Describe,
+    Explain,
+    /// `SET` statements and inline `SETTINGS` clauses.
+    Set,
+    Use,
+    System,
+    Other,
+}
+
+/// Allowlist based statement validation for SQL coming from semi-trusted callers.
+///
+/// Statements are checked lexically: table references following `FROM`, `JOIN`, `INTO`,
+/// `TABLE` and the commas of a `FROM` clause must be on the allowlist, table functions (`file()`, `url()`, `remote()`...),
+/// engines reaching other tables or outside data (`Merge`, `Buffer`, `URL`...) and dictionary
+/// sources are rejected and so is every statement kind not explicitly allowed, as are
+/// user-defined functions, which every database sees. `WITH` names only shadow tables within
+/// their own scope. Any other `a.b` name must start with an allowed database or a table or
+/// alias of the statement, so subcolumns need the table: `t.n.x` rather than `n.x`. The check
+/// is conservative, unusual but harmless SQL may be rejected.
+#[derive(Debug, Clone)]
+pub struct Guard {
+    statements: HashSet<StatementKind>,
+    databases: HashSet<String>,
+    tables: HashSet<String>,
+    default_database: String,
+}
+
+struct TableRef<'a> {
+    database: Option<&'a str>,
+    table: &'a str,
+}
+
+/// Names a statement defines: `WITH` names with the token ranges they are visible in, and the
+/// tables and aliases found in table positions, which may qualify other names.
+struct Names<'a> {
+    ctes: Vec<(&'a str, Range<usize>)>,
+    declared: HashSet<&'a str>,
+}
+
+/// Parenthesized part of a statement being scanned, or the statement itself.
+#[derive(Debug, Default)]
+struct Scope {
+    /// Arguments of `extract(... FROM ...)` and the like.
+    from_function: bool,
+    /// Inside a `FROM` clause, where a comma starts another table expression.
+    from_clause: bool,
+    /// Inside `ARRAY JOIN`, where a comma starts another array expression.
+    array_join: bool,
+}
+
+/// Functions that take `FROM` as part of their argument syntax.
+const FROM_FUNCTIONS: &[&str] = &["EXTRACT", "TRIM", "SUBSTRING"];
+
+/// Keywords ending a `FROM` clause.
+const FROM_END_KEYWORDS: &[&str] = &[
+    "EXCEPT",
+    "FORMAT",
+    "GROUP",
+    "HAVING",
+    "INTERSECT",
+    "INTO",
+    "LIMIT",
+    "ORDER",
+    "PREWHERE",
+    "QUALIFY",
+    "SELECT",
+    "SETTINGS",
+    "UNION",
+    "WHERE",
+    "WINDOW",
+];
+
+/// Functions reaching outside the allowed tables, in any position.
+const DENIED_FUNCTIONS: &[&str] = &[
+    "azureBlobStorage",
+    "cluster",
+    "clusterAllReplicas",
+    "executable",
+    "file",
+    "hdfs",
+    "input",
+    "jdbc",
+    "joinGet",
+    "joinGetOrNull",
+    "merge",
+    "mysql",
+    "odbc",
+    "postgresql",
+    "remote",
+    "remoteSecure",
+    "s3",
+    "s3Cluster",
+    "sqlite",
+    "url",
+];
+
+/// Table and database engines reading or writing data outside the table itself, e.g.
+/// `Merge('db', '.*')` or `Buffer(db, t, ...)`.
+const DENIED_ENGINES: &[&str] = &[
+    "AzureBlobStorage",
+    "Buffer",
+    "DeltaLake",
+    "Dictionary",
+    "Distributed",
+    "Executable",
+    "ExecutablePool",
+    "File",
+    "HDFS",
+    "Hudi",
+    "Iceberg",
+    "JDBC",
+    "Kafka",
+    "MaterializedPostgreSQL",
+    "Merge",
+    "MongoDB",
+    "MySQL",
+    "NATS",
+    "ODBC",
+    "PostgreSQL",
+    "RabbitMQ",
+    "Redis",
+    "S3",
+    "S3Queue",
+    "SQLite",
+    "URL",
+];
+
+impl StatementKind {
+    pub fn of(statement: &str) -> Self {
+        let tokens = tokenize(statement);
+        match tokens.first() {
+            Some(Token::Punct(b'(')) => Self::Select,
+            Some(Token::Word(word)) => Self::from_keyword(word),
+            _ => Self::Other,
+        }
+    }
+
+    fn from_keyword(word: &str) -> Self {
+        match word.to_ascii_uppercase().as_str() {
+            "SELECT" | "WITH" => Self::Select,
+            "INSERT" => Self::Insert,
+            "CREATE" => Self::Create,
+            "ALTER" => Self::Alter,
+            "DROP" => Self::Drop,
+            "TRUNCATE" => Self::Truncate,
+            "RENAME" | "EXCHANGE" => Self::Rename,
+            "ATTACH" => Self::Attach,
+            "DETACH" => Self::Detach,
+            "OPTIMIZE" => Self::Optimize,
+            "SHOW" | "EXISTS" => Self::Show,
+            "DESCRIBE" | "DESC" => Self::Describe,
+            "EXPLAIN" => Self::Explain,
+            "SET" => Self::Set,
+            "USE" => Self::Use,
+            "SYSTEM" => Self::System,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Guard {
+    /// Allows nothing until statement kinds are added. Unqualified tables resolve to
+    /// "default", which is always allowed.
+    pub fn new() -> Self {
+        Self {
+            statements: HashSet::new(),
+            databases: HashSet::new(),
+            tables: HashSet::new(),
+            default_database: "default".to_string(),
+        }
+    }
+
+    /// `Select`, `Show`, `Describe` and `Explain` statements.
+    pub fn read_only() -> Self {
+        Self::new()
+            .with_statement(StatementKind::Select)
+            .with_statement(StatementKind::Show)
+            .with_statement(StatementKind::Describe)
+            .with_statement(StatementKind::Explain)
+    }
+
+    pub fn with_statement(mut self, kind: StatementKind) -> Self {
+        self.statements.insert(kind);
+        self
+    }
+
+    pub fn with_database(mut self, database: &str) -> Self {
+        self.databases.insert(database.to_string());
+        self
+    }
+
+    /// Restricts access to the listed tables, either "table" (in any allowed database) or
+    /// "db.table". Without any table every table of the allowed databases is accessible.
+    pub fn with_table(mut self, table: &str) -> Self {
+        self.tables.insert(table.to_string());
+        self
+    }
+
+    /// Database unqualified names resolve to, it should match the session's database.
+    pub fn with_default_database(mut self, database: &str) -> Self {
+        self.default_database = database.to_string();
+        self
+    }
+
+    /// Validates every statement of `sql`, failing with `Error::Forbidden` on the first
+    /// violation.
+    pub fn check(&self, sql: &str) -> Result<(), Error> {
+        for statement in split_statements(sql) {
+            self.check_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Checks `query` and runs it on `session`.
+    pub fn execute(
+        &self,
+        session: &Session,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        self.check(query)?;
+        session.execute(query, query_args)
+    }
+
+    fn check_statement(&self, statement: &str) -> Result<(), Error> {
+        let kind = StatementKind::of(statement);
+        if !self.statements.contains(&kind) {
+            return Err(forbidden(format!("{:?} statements", kind)));
+        }
+
+        let tokens = tokenize(statement);
+        let mut names = Names {
+            ctes: cte_scopes(&tokens),
+            declared: HashSet::new(),
+        };
+
+        // user-defined functions are visible to every database
+        let object = if tokens.get(1).is_some_and(|t| t.is_word("OR")) {
+            3
+        } else {
+            1
+        };
+        if matches!(kind, StatementKind::Create | StatementKind::Drop)
+            && tokens.get(object).is_some_and(|t| t.is_word("FUNCTION"))
+        {
+            return Err(forbidden("user-defined functions".to_string()));
+        }
+        let names_objects = matches!(
+            kind,
+            StatementKind::Create
+                | StatementKind::Alter
+                | StatementKind::Drop
+                | StatementKind::Truncate
+                | StatementKind::Rename
+                | StatementKind::Attach
+                | StatementKind::Detach
+                | StatementKind::Optimize
+                | StatementKind::Describe
+                | StatementKind::Show
+        );
+        let mut scopes = vec![Scope::default()];
+        let mut lists_databases = false;
+        let mut end = tokens.len();
+        let mut i = 0;
+
+        // `DESCRIBE t`, `TRUNCATE t` and `EXISTS t` name the table right away
+        let names_table_first = matches!(kind, StatementKind::Describe | StatementKind::Truncate)
+            || tokens.first().is_some_and(|t| t.is_word("EXISTS"));
+        let object_keyword = tokens.get(1).is_some_and(|t| {
+            ["TABLE", "VIEW", "DICTIONARY", "DATABASE", "TEMPORARY"]
+                .iter()
+                .any(|k| t.is_word(k))
+        });
+        if names_table_first && !object_keyword {
+            i = self.check_table_at(&tokens, 1, &mut names)?;
+        } else if tokens.first().is_some_and(|t| t.is_word("EXCHANGE")) {
+            // `EXCHANGE TABLES a AND b`
+            let at = if tokens
+                .get(1)
+                .is_some_and(|t| t.is_word("TABLES") || t.is_word("DICTIONARIES"))
+            {
+                2
+            } else {
+                1
+            };
+            i = self.check_table_at(&tokens, at, &mut names)?;
+            if !tokens.get(i).is_some_and(|t| t.is_word("AND")) {
+                return Err(forbidden("unrecognized EXCHANGE statement".to_string()));
+            }
+            i = self.check_table_at(&tokens, i + 1, &mut names)?;
+        }
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+            let depth = scopes.len();
+            let scope = scopes.last_mut().expect("statement scope");
+            if FROM_END_KEYWORDS.iter().any(|k| token.is_word(k)) {
+                scope.from_clause = false;
+                scope.array_join = false;
+            }
+            let in_from_function = scope.from_function;
+
+            match token {
+                Token::Quoted(name) if name.contains('\\') => {
+                    return Err(forbidden(format!("escaped identifier {}", name)));
+                }
+                Token::Word(name)
+                    if tokens.get(i + 1) == Some(&Token::Punct(b'('))
+                        && is_denied_function(name) =>
+                {
+                    return Err(forbidden(format!("function {}", name)));
+                }
+                Token::Punct(b'(') => {
+                    let is_from_function =
+                        i > 0 && FROM_FUNCTIONS.iter().any(|f| tokens[i - 1].is_word(f));
+                    scopes.push(Scope {
+                        from_function: is_from_function,
+                        ..Scope::default()
+                    });
+                }
+                Token::Punct(b')') if depth > 1 => {
+                    scopes.pop();
+                }
+                Token::Punct(b',') if scope.from_clause && !scope.array_join => {
+                    // `FROM a, b`, also after subqueries, `SAMPLE`, `FINAL` and `JOIN ... ON`
+                    i = self.check_table_at(&tokens, i + 1, &mut names)?;
+                    continue;
+                }
+                Token::Punct(b',')
+                    if depth == 1
+                        && matches!(kind, StatementKind::Drop | StatementKind::Rename) =>
+                {
+                    // `DROP TABLE a, b` and `RENAME TABLE a TO b, c TO d`
+                    i = if lists_databases {
+                        self.check_database_at(&tokens, i + 1)?
+                    } else {
+                        self.check_table_at(&tokens, i + 1, &mut names)?
+                    };
+                    continue;
+                }
+                Token::Word(_)
+                    if token.is_word("SETTINGS")
+                        && !self.statements.contains(&StatementKind::Set) =>
+                {
+                    return Err(forbidden("SETTINGS clause".to_string()));
+                }
+                Token::Word(_) if token.is_word("ENGINE") => {
+                    let at = if tokens.get(i + 1) == Some(&Token::Punct(b'=')) {
+                        i + 2
+                    } else {
+                        i + 1
+                    };
+                    if let Some(engine) = tokens.get(at).and_then(Token::name) {
+                        if DENIED_ENGINES
+                            .iter()
+                            .any(|e| e.eq_ignore_ascii_case(engine))
+                        {
+                            return Err(forbidden(format!("engine {}", engine)));
+                        }
+                    }
+                }
+                Token::Word(_)
+                    if token.is_word("SOURCE")
+                        && tokens.get(i + 1) == Some(&Token::Punct(b'(')) =>
+                {
+                    // dictionary sources name databases, hosts and files in string literals
+                    return Err(forbidden("dictionary sources".to_string()));
+                }
+                Token::Word(_) if token.is_word("FORMAT") && kind == StatementKind::Insert => {
+                    // inline data follows
+                    end = i;
+                    break;
+                }
+                Token::Word(_) if token.is_word("IN") && kind == StatementKind::Show => {
+                    i = self.check_database_at(&tokens, i + 1)?;
+                    continue;
+                }
+                Token::Word(_) if token.is_word("IN") => {
+                    // `x IN table` reads the whole table
+                    let is_table = tokens.get(i + 1).and_then(Token::name).is_some()
+                        && tokens.get(i + 2) != Some(&Token::Punct(b'('));
+                    if is_table {
+                        i = self.check_table_at(&tokens, i + 1, &mut names)?;
+                        continue;
+                    }
+                }
+                Token::Word(_) if token.is_word("FROM") && !in_from_function => {
+                    if kind == StatementKind::Show {
+                        i = self.check_database_at(&tokens, i + 1)?;
+                        continue;
+                    }
+                    scope.from_clause = true;
+                    scope.array_join = false;
+                    i = self.check_table_at(&tokens, i + 1, &mut names)?;
+                    continue;
+                }
+                Token::Word(_) if token.is_word("JOIN") => {
+                    let is_array_join = i > 0 && tokens[i - 1].is_word("ARRAY");
+                    scope.array_join = is_array_join;
+                    if !is_array_join {
+                        i = self.check_table_at(&tokens, i + 1, &mut names)?;
+                        continue;
+                    }
+                }
+                Token::Word(_) if token.is_word("INTO") => {
+                    if tokens.get(i + 1).is_some_and(|t| t.is_word("OUTFILE")) {
+                        return Err(forbidden("INTO OUTFILE".to_string()));
+                    }
+                    i = self.check_table_at(
+                        &tokens,
+                        skip_table_keyword(&tokens, i + 1),
+                        &mut names,
+                    )?;
+                    continue;
+                }
+                Token::Word(_) if names_objects && token.is_word("TO") => {
+                    i = self.check_table_at(
+                        &tokens,
+                        skip_table_keyword(&tokens, i + 1),
+                        &mut names,
+                    )?;
+                    continue;
+                }
+                Token::Word(_)
+                    if names_objects
+                        && ["TABLE", "VIEW", "DICTIONARY"]
+                            .iter()
+                            .any(|k| token.is_word(k)) =>
+                {
+                    let at = skip_if_exists(&tokens, i + 1);
+                    i = self.check_table_ref(
+                        &tokens,
+                        at,
+                        &mut names,
+                        kind == StatementKind::Create,
+                    )?;
+                    // `CREATE TABLE t AS other` copies the structure of `other`
+                    let copies_table = kind == StatementKind::Create
+                        && tokens.get(i).is_some_and(|t| t.is_word("AS"))
+                        && !tokens
+                            .get(i + 1)
+                            .is_some_and(|t| t.is_word("SELECT") || t.is_word("WITH"));
+                    if copies_table {
+                        i = self.check_table_at(&tokens, i + 1, &mut names)?;
+                    }
+                    continue;
+                }
+                Token::Word(_) if names_objects && token.is_word("DATABASE") => {
+                    lists_databases = true;
+                    i = self.check_database_at(&tokens, skip_if_exists(&tokens, i + 1))?;
+                    continue;
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        self.check_qualifiers(&tokens[..end], &names)
+    }
+
+    /// Checks the qualifier of every `a.b` name outside table positions: it must be an allowed
+    /// database, a `WITH` name or a table or alias of the statement.
+    fn check_qualifiers(&self, tokens: &[Token], names: &Names) -> Result<(), Error> {
+        for (j, window) in tokens.windows(3).enumerate() {
+            let Some(qualifier) = window[0].name() else {
+                continue;
+            };
+            let qualifies = window[1] == Token::Punct(b'.')
+                && (window[2].name().is_some() || window[2] == Token::Punct(b'*'));
+            if !qualifies || (j > 0 && tokens[j - 1] == Token::Punct(b'.')) {
+                continue;
+            }
+
+            let is_known = names.declared.contains(qualifier)
+                || names.ctes.iter().any(|(name, _)| *name == qualifier);
+            if !is_known {
+                self.check_database(qualifier)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the table reference starting at `i`, returns the index after it. Subqueries
+    /// are left for the main loop.
+    fn check_table_at<'a>(
+        &self,
+        tokens: &[Token<'a>],
+        i: usize,
+        names: &mut Names<'a>,
+    ) -> Result<usize, Error> {
+        self.check_table_ref(tokens, i, names, false)
+    }
+
+    /// `check_table_at` for names a column list may follow, as in `CREATE TABLE t (...)`.
+    fn check_table_ref<'a>(
+        &self,
+        tokens: &[Token<'a>],
+        i: usize,
+        names: &mut Names<'a>,
+        columns_may_follow: bool,
+    ) -> Result<usize, Error> {
+        if tokens.get(i) == Some(&Token::Punct(b'(')) {
+            if let Some(alias) = alias_at(tokens, closing_paren(tokens, i) + 1) {
+                names.declared.insert(alias);
+            }
+            return Ok(i);
+        }
+        if tokens.get(i).is_some_and(|t| t.is_word("FUNCTION")) {
+            return Err(forbidden("table functions".to_string()));
+        }
+
+        let (table, next) = table_ref(tokens, i)
+            .ok_or_else(|| forbidden("unrecognized table reference".to_string()))?;
+
+        if !columns_may_follow && tokens.get(next) == Some(&Token::Punct(b'(')) {
+            return Err(forbidden(format!("table function {}", table.table)));
+        }
+
+        names.declared.insert(table.table);
+        if let Some(alias) = alias_at(tokens, next) {
+            names.declared.insert(alias);
+        }
+
+        let is_cte = names
+            .ctes
+            .iter()
+            .any(|(name, scope)| *name == table.table && scope.contains(&i));
+        if table.database.is_none() && is_cte {
+            return Ok(next);
+        }
+
+        let database = table.database.unwrap_or(&self.default_database);
+        self.check_database(database)?;
+
+        if !self.tables.is_empty()
+            && !self.tables.contains(table.table)
+            && !self
+                .tables
+                .contains(&format!("{}.{}", database, table.table))
+        {
+            return Err(forbidden(format!("table {}.{}", database, table.table)));
+        }
+
+        Ok(next)
+    }
+
+    fn check_database_at(&self, tokens: &[Token], i: usize) -> Result<usize, Error> {
+        let database = tokens
+            .get(i)
+            .and_then(Token::name)
+            .ok_or_else(|| forbidden("unrecognized database reference".to_string()))?;
+        self.check_database(database)?;
+        Ok(i + 1)
+    }
+
+    fn check_database(&self, database: &str) -> Result<(), Error> {
+        if database == self.default_database || self.databases.contains(database) {
+            return Ok(());
+        }
+        Err(forbidden(format!("database {}", database)))
+    }
+}
+
+impl Default for Guard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn forbidden(what: String) -> Error {
+    Error::Forbidden(what)
+}
+
+fn is_denied_function(name: &str) -> bool {
+    name.get(..4)
+        .is_some_and(|p| p.eq_ignore_ascii_case("dict"))
+        || DENIED_FUNCTIONS
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(name))
+}
+
+fn table_ref<'a>(tokens: &[Token<'a>], i: usize) -> Option<(TableRef<'a>, usize)> {
+    let first = tokens.get(i)?.name()?;
+
+    if tokens.get(i + 1) == Some(&Token::Punct(b'.')) {
+        let second = tokens.get(i + 2)?.name()?;
+        return Some((
+            TableRef {
+                database: Some(first),
+                table: second,
+            },
+            i + 3,
+        ));
+    }
+
+    Some((
+        TableRef {
+            database: None,
+            table: first,
+        },
+        i + 1,
+    ))
+}
+
+/// Alias given by `AS x` or a bare `x` at `i`, after a table reference.
+fn alias_at<'a>(tokens: &[Token<'a>], i: usize) -> Option<&'a str> {
+    let token = tokens.get(i)?;
+    if token.is_word("AS") {
+        return tokens.get(i + 1)?.name();
+    }
+    token
+        .name()
+        .filter(|w| !CLAUSE_KEYWORDS.iter().any(|k| w.eq_ignore_ascii_case(k)))
+}
+
+/// Index of the parenthesis closing the one at `open`, or the end of `tokens`.
+fn closing_paren(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct(b'(') => depth += 1,
+            Token::Punct(b')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Index after an optional `IF [NOT] EXISTS` starting at `i`.
+fn skip_if_exists(tokens: &[Token], mut i: usize) -> usize {
+    if tokens.get(i).is_some_and(|t| t.is_word("IF")) {
+        i += 1;
+        if tokens.get(i).is_some_and(|t| t.is_word("NOT")) {
+            i += 1;
+        }
+        if tokens.get(i).is_some_and(|t| t.is_word("EXISTS")) {
+            i += 1;
+        }
+    }
+    i
+}
+
+fn skip_table_keyword(tokens: &[Token], i: usize) -> usize {
+    if tokens.get(i).is_some_and(|t| t.is_word("TABLE")) {
+        i + 1
+    } else {
+        i
+    }
+}
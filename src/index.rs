@@ -0,0 +1,108 @@
+use crate::error::Error;
+use crate::sql::quote_identifier;
+use crate::table::Table;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Data skipping index types.
+#[derive(Debug, Clone, Copy)]
+pub enum IndexType {
+    MinMax,
+    /// Stores up to `max_rows` distinct values per granule, 0 means unlimited.
+    Set(u64),
+    /// Bloom filter with the given false positive rate.
+    BloomFilter(f64),
+    /// Bloom filter over n-grams, used by `LIKE` and `multiSearchAny`.
+    NgramBF {
+        n: u8,
+        size_bytes: u64,
+        hash_functions: u8,
+        seed: u64,
+    },
+    /// Bloom filter over tokens, used by `hasToken`.
+    TokenBF {
+        size_bytes: u64,
+        hash_functions: u8,
+        seed: u64,
+    },
+}
+
+/// Data skipping index as reported by `system.data_skipping_indices`.
+#[derive(Debug, Clone)]
+pub struct SkippingIndex {
+    pub name: String,
+    pub type_full: String,
+    pub expr: String,
+    pub granularity: u64,
+}
+
+impl IndexType {
+    pub fn to_sql(self) -> String {
+        match self {
+            Self::MinMax => "minmax".to_string(),
+            Self::Set(max_rows) => format!("set({})", max_rows),
+            Self::BloomFilter(false_positive) => format!("bloom_filter({})", false_positive),
+            Self::NgramBF {
+                n,
+                size_bytes,
+                hash_functions,
+                seed,
+            } => format!(
+                "ngrambf_v1({}, {}, {}, {})",
+                n, size_bytes, hash_functions, seed
+            ),
+            Self::TokenBF {
+                size_bytes,
+                hash_functions,
+                seed,
+            } => format!("tokenbf_v1({}, {}, {})", size_bytes, hash_functions, seed),
+        }
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Adds a data skipping index over `expr`. Existing parts are not indexed until
+    /// `materialize_index` is called.
+    pub fn add_index(
+        &self,
+        name: &str,
+        expr: &str,
+        index_type: IndexType,
+        granularity: u64,
+    ) -> Result<(), Error> {
+        self.alter(&format!(
+            "ADD INDEX IF NOT EXISTS {} {} TYPE {} GRANULARITY {}",
+            quote_identifier(name),
+            expr,
+            index_type.to_sql(),
+            granularity
+        ))
+    }
+
+    pub fn materialize_index(&self, name: &str) -> Result<(), Error> {
+        self.alter(&format!("MATERIALIZE INDEX {}", quote_identifier(name)))
+    }
+
+    pub fn drop_index(&self, name: &str) -> Result<(), Error> {
+        self.alter(&format!("DROP INDEX IF EXISTS {}", quote_identifier(name)))
+    }
+
+    pub fn indexes(&self) -> Result<Vec<SkippingIndex>, Error> {
+        let rows = self.session().query_rows(&format!(
+            "SELECT name, type_full, expr, granularity FROM system.data_skipping_indices WHERE {} ORDER BY name",
+            self.system_filter()
+        ))?;
+
+        rows.into_iter()
+            .map(|row| match <[String; 4]>::try_from(row) {
+                Ok([name, type_full, expr, granularity]) => Ok(SkippingIndex {
+                    name,
+                    type_full,
+                    expr,
+                    granularity: parse_u64(&granularity)?,
+                }),
+                Err(row) => Err(unexpected_columns(4, row.len())),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use crate::arg::Arg;
+use crate::session::Session;
+
+/// Statement about to be executed, see `Session::with_interceptor`.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub sql: String,
+    pub args: Vec<Arg<'static>>,
+}
+
+/// Rewrites or annotates statements before a session executes them.
+pub trait StatementInterceptor: Send + Sync {
+    fn intercept(&self, statement: &mut Statement);
+}
+
+impl<F> StatementInterceptor for F
+where
+    F: Fn(&mut Statement) + Send + Sync,
+{
+    fn intercept(&self, statement: &mut Statement) {
+        self(statement)
+    }
+}
+
+impl Statement {
+    /// Adds a query level setting, e.g. `log_comment` or `max_execution_time`.
+    pub fn set(&mut self, name: &str, value: impl ToString) {
+        self.args.push(Arg::Custom(
+            name.to_string().into(),
+            Some(value.to_string().into()),
+        ));
+    }
+}
+
+impl Session {
+    /// Runs `interceptor` on every statement passed to `execute`, after the ones added before.
+    ///
+    /// Audit entries, hooks and transforms see the rewritten statement.
+    pub fn with_interceptor(mut self, interceptor: impl StatementInterceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+}
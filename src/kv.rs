@@ -0,0 +1,85 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::tsv::unexpected_columns;
+
+/// Key-value store backed by a ReplacingMergeTree table.
+///
+/// Every write appends a new row versioned by the insertion time, deletes
+/// append a tombstone. Reads use FINAL so only the latest version is seen.
+pub struct KvStore<'a> {
+    session: &'a Session,
+    table: String,
+}
+
+impl<'a> KvStore<'a> {
+    /// Opens the store, creating the backing table if it does not exist.
+    pub fn open(session: &'a Session, name: &str) -> Result<Self, Error> {
+        let table = quote_qualified(name);
+
+        session.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (key String, value String, version UInt64, deleted UInt8) \
+                 ENGINE = ReplacingMergeTree(version, deleted) ORDER BY key",
+                table
+            ),
+            None,
+        )?;
+
+        Ok(Self { session, table })
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT value FROM {} FINAL WHERE key = {} AND deleted = 0",
+            self.table,
+            quote_string(key)
+        ))?;
+
+        Ok(rows
+            .into_iter()
+            .next()
+            .and_then(|row| row.into_iter().next()))
+    }
+
+    pub fn put(&self, key: &str, value: &str) -> Result<(), Error> {
+        self.write(key, value, false)
+    }
+
+    pub fn delete(&self, key: &str) -> Result<(), Error> {
+        self.write(key, "", true)
+    }
+
+    /// Returns all live entries whose key starts with `prefix`, ordered by key.
+    pub fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT key, value FROM {} FINAL WHERE startsWith(key, {}) AND deleted = 0 ORDER BY key",
+            self.table,
+            quote_string(prefix)
+        ))?;
+
+        rows.into_iter()
+            .map(|row| match <[String; 2]>::try_from(row) {
+                Ok([key, value]) => Ok((key, value)),
+                Err(row) => Err(unexpected_columns(2, row.len())),
+            })
+            .collect()
+    }
+
+    fn write(&self, key: &str, value: &str, deleted: bool) -> Result<(), Error> {
+        self.session.execute(
+            &format!(
+                "INSERT INTO {} (key, value, version, deleted) \
+                 SELECT {}, {}, toUnixTimestamp64Nano(now64(9)), {}",
+                self.table,
+                quote_string(key),
+                quote_string(value),
+                deleted as u8
+            ),
+            None,
+        )?;
+
+        Ok(())
+    }
+}
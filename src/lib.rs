@@ -1,4 +1,7 @@
+pub mod advisor;
 pub mod arg;
+pub mod attach;
+pub mod audit;
 #[allow(
     dead_code,
     unused,
@@ -7,19 +10,95 @@ pub mod arg;
     non_upper_case_globals
 )]
 mod bindings;
+pub mod blocking;
+pub mod cache;
+pub mod catalog;
+pub mod checksum;
+pub mod config;
+pub mod convert;
+pub mod coordinator;
+pub mod copy;
+pub mod datasets;
+pub mod diff;
 pub mod error;
+pub mod explain;
+pub mod export;
 pub mod format;
+pub mod guard;
+pub mod index;
+pub mod intercept;
+pub mod kv;
 pub mod log_level;
+pub mod metrics;
+pub mod nested;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod outbox;
+pub mod profile;
+pub mod projection;
+pub mod query;
+pub mod query_log;
 pub mod query_result;
+pub mod quota;
+#[cfg(feature = "serde")]
+pub mod quoted;
+#[cfg(feature = "serde")]
+pub mod register;
+pub mod remote;
+pub mod ring_buffer;
+pub mod rollup;
+pub mod scheduler;
+pub mod schema;
+pub mod search;
 pub mod session;
+pub mod settings;
+mod shortcuts;
+pub mod snapshot;
+pub mod sql;
+pub mod stats;
+mod suggest;
+pub mod sync;
+pub mod table;
+mod temp;
+pub mod tenant;
+pub mod timeseries;
+pub mod transform;
+mod tsv;
+pub mod vector;
+mod warm_up;
+#[cfg(feature = "xlsx")]
+mod xlsx;
 
 use std::ffi::{c_char, CString};
+use std::sync::Mutex;
+
+pub use crate::catalog::query_over;
+pub use crate::catalog::query_over_named;
+pub use crate::convert::convert;
+pub use crate::copy::copy;
+pub use crate::diff::diff;
+#[cfg(feature = "serde")]
+pub use crate::shortcuts::execute_json;
+#[cfg(feature = "serde")]
+pub use crate::shortcuts::execute_rows;
+pub use crate::shortcuts::execute_scalar;
 
 use crate::arg::Arg;
 use crate::error::Error;
+use crate::format::OutputFormat;
 use crate::query_result::QueryResult;
 
-pub fn execute(query: &str, query_args: Option<&[Arg]>) -> Result<Option<QueryResult>, Error> {
+static DEFAULT_FORMAT: Mutex<Option<OutputFormat>> = Mutex::new(None);
+
+/// Output format of `execute` calls that don't pass `Arg::OutputFormat`, process-wide.
+///
+/// `None` restores the engine default (TabSeparated). Sessions have their own,
+/// see `SessionBuilder::with_default_format`.
+pub fn set_default_format(format: Option<OutputFormat>) {
+    *DEFAULT_FORMAT.lock().unwrap_or_else(|e| e.into_inner()) = format;
+}
+
+pub fn execute(query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
     let mut argv = Vec::with_capacity(query_args.as_ref().map_or(0, |v| v.len()) + 2);
     argv.push(arg_clickhouse()?.into_raw());
 
@@ -29,20 +108,40 @@ pub fn execute(query: &str, query_args: Option<&[Arg]>) -> Result<Option<QueryRe
         }
     }
 
+    let default_format = *DEFAULT_FORMAT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(arg) = default_format_arg(query_args, default_format) {
+        argv.push(arg.to_cstring()?.into_raw());
+    }
+
+    #[cfg(feature = "otel")]
+    if let Some(arg) = otel::traceparent_arg() {
+        argv.push(arg.to_cstring()?.into_raw());
+    }
+
     argv.push(arg_query(query)?.into_raw());
     call_chdb(argv)
 }
 
-fn call_chdb(mut argv: Vec<*mut c_char>) -> Result<Option<QueryResult>, Error> {
+/// `format` as an argument unless `query_args` already pick an output format.
+pub(crate) fn default_format_arg(
+    query_args: Option<&[Arg]>,
+    format: Option<OutputFormat>,
+) -> Option<Arg<'static>> {
+    let has_format =
+        query_args.is_some_and(|args| args.iter().any(|arg| matches!(arg, Arg::OutputFormat(_))));
+    format.filter(|_| !has_format).map(Arg::OutputFormat)
+}
+
+fn call_chdb(mut argv: Vec<*mut c_char>) -> Result<QueryResult, Error> {
     let argc = argv.len() as i32;
     let argv = argv.as_mut_ptr();
     let result_ptr = unsafe { bindings::query_stable_v2(argc, argv) };
 
     if result_ptr.is_null() {
-        return Ok(None);
+        return Err(Error::NoResult);
     }
 
-    Ok(Some(QueryResult(result_ptr).check_error()?))
+    QueryResult::from_engine(result_ptr).check_error()
 }
 
 fn arg_clickhouse() -> Result<CString, Error> {
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::session::Session;
+use crate::tsv::unexpected_columns;
+
+/// Values of `system.metrics`, `system.events` and `system.asynchronous_metrics` at one point in time.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub metrics: HashMap<String, i64>,
+    pub events: HashMap<String, u64>,
+    pub asynchronous_metrics: HashMap<String, f64>,
+}
+
+impl MetricsSnapshot {
+    /// Bytes of memory tracked by the engine.
+    pub fn memory_tracking(&self) -> i64 {
+        self.metric("MemoryTracking")
+    }
+
+    /// Merges currently running.
+    pub fn merges(&self) -> i64 {
+        self.metric("Merge")
+    }
+
+    /// Files currently open for reading or writing.
+    pub fn open_files(&self) -> i64 {
+        self.metric("OpenFileForRead") + self.metric("OpenFileForWrite")
+    }
+
+    pub fn selected_rows(&self) -> u64 {
+        self.event("SelectedRows")
+    }
+
+    pub fn inserted_rows(&self) -> u64 {
+        self.event("InsertedRows")
+    }
+
+    pub fn merged_rows(&self) -> u64 {
+        self.event("MergedRows")
+    }
+
+    pub fn metric(&self, name: &str) -> i64 {
+        self.metrics.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn event(&self, name: &str) -> u64 {
+        self.events.get(name).copied().unwrap_or(0)
+    }
+}
+
+impl Session {
+    pub fn metrics(&self) -> Result<MetricsSnapshot, Error> {
+        let rows = self.query_rows(
+            "SELECT 'metric', metric, toString(value) FROM system.metrics \
+             UNION ALL SELECT 'event', event, toString(value) FROM system.events \
+             UNION ALL SELECT 'async', metric, toString(value) FROM system.asynchronous_metrics",
+        )?;
+
+        let mut snapshot = MetricsSnapshot::default();
+        for row in rows {
+            let [kind, name, value] =
+                <[String; 3]>::try_from(row).map_err(|row| unexpected_columns(3, row.len()))?;
+            let invalid = || Error::InvalidData(format!("invalid value for {}: {}", name, value));
+
+            match kind.as_str() {
+                "metric" => {
+                    let value = value.parse().map_err(|_| invalid())?;
+                    snapshot.metrics.insert(name, value);
+                }
+                "event" => {
+                    let value = value.parse().map_err(|_| invalid())?;
+                    snapshot.events.insert(name, value);
+                }
+                _ => {
+                    let value = value.parse().map_err(|_| invalid())?;
+                    snapshot.asynchronous_metrics.insert(name, value);
+                }
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
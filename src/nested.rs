@@ -0,0 +1,99 @@
+//! Flattening of `Nested` columns into one row per nested element.
+
+use crate::error::Error;
+use crate::schema::Column;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+
+impl Session {
+    /// `query` rewritten to return one row per element of its `Nested` columns, see
+    /// `flatten_query`.
+    ///
+    /// Sibling arrays whose lengths differ in any row of the result are plain arrays that
+    /// happen to share a prefix and are left alone. Checking that runs `query` once.
+    pub fn flatten_nested(&self, query: &str) -> Result<String, Error> {
+        let columns: Vec<Column> = self
+            .describe(query)?
+            .into_iter()
+            .map(|column| Column {
+                name: column.name,
+                type_name: column.type_name,
+            })
+            .collect();
+
+        let mut groups = nested_groups(&columns);
+        let checks: Vec<String> = groups
+            .iter()
+            .map(|members| {
+                let first = quote_identifier(members[0]);
+                let mismatches: Vec<String> = members[1..]
+                    .iter()
+                    .map(|member| {
+                        format!("length({}) != length({})", first, quote_identifier(member))
+                    })
+                    .collect();
+                if mismatches.is_empty() {
+                    "0".to_string()
+                } else {
+                    format!("countIf({})", mismatches.join(" OR "))
+                }
+            })
+            .collect();
+        if !checks.is_empty() {
+            let rows =
+                self.query_rows(&format!("SELECT {} FROM ({})", checks.join(", "), query))?;
+            let counts = rows.into_iter().next().unwrap_or_default();
+            let mut counts = counts.iter();
+            groups.retain(|_| counts.next().is_some_and(|count| count == "0"));
+        }
+
+        Ok(array_join(query, groups))
+    }
+}
+
+/// Wraps `query`, whose result has `columns`, in one `LEFT ARRAY JOIN` per `Nested` column so
+/// struct-based decoders see plain values instead of parallel arrays.
+///
+/// A `Nested` column is one of type `Nested(...)` or two or more `Array` columns named
+/// `<prefix>.<field>`; a lone dotted array is an ordinary column. Several nested columns
+/// multiply into every combination of their elements. Rows with empty nested values are
+/// kept with defaults. Queries without nested columns are returned as is.
+pub fn flatten_query(query: &str, columns: &[Column]) -> String {
+    array_join(query, nested_groups(columns))
+}
+
+/// Members of every nested column among `columns`.
+fn nested_groups(columns: &[Column]) -> Vec<Vec<&str>> {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for column in columns {
+        let prefix = match column.name.split_once('.') {
+            Some((prefix, _)) if column.type_name.starts_with("Array(") => prefix,
+            _ if column.type_name.starts_with("Nested(") => column.name.as_str(),
+            _ => continue,
+        };
+        match groups.iter_mut().find(|(name, _)| *name == prefix) {
+            Some((_, members)) => members.push(&column.name),
+            None => groups.push((prefix, vec![&column.name])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(prefix, members)| members.len() > 1 || members[0] == *prefix)
+        .map(|(_, members)| members)
+        .collect()
+}
+
+fn array_join(query: &str, groups: Vec<Vec<&str>>) -> String {
+    if groups.is_empty() {
+        return query.to_string();
+    }
+
+    let mut sql = format!("SELECT * FROM ({})", query);
+    for members in groups {
+        let members: Vec<String> = members.into_iter().map(quote_identifier).collect();
+        sql.push_str(" LEFT ARRAY JOIN ");
+        sql.push_str(&members.join(", "));
+    }
+    sql
+}
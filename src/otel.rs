@@ -0,0 +1,69 @@
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Context;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_string;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Span recorded by the engine in `system.opentelemetry_span_log`.
+#[derive(Debug, Clone)]
+pub struct EngineSpan {
+    pub span_id: u64,
+    pub parent_span_id: u64,
+    pub operation_name: String,
+    pub start_time_us: u64,
+    pub finish_time_us: u64,
+}
+
+/// `--opentelemetry-traceparent` for the span active in the current context, if any.
+pub(crate) fn traceparent_arg() -> Option<Arg<'static>> {
+    let context = Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return None;
+    }
+
+    Some(Arg::Custom(
+        "opentelemetry-traceparent".into(),
+        Some(
+            format!(
+                "00-{:032x}-{:016x}-{:02x}",
+                span_context.trace_id(),
+                span_context.span_id(),
+                span_context.trace_flags().to_u8()
+            )
+            .into(),
+        ),
+    ))
+}
+
+impl Session {
+    /// Engine spans of a trace, `trace_id` in its 32 hex digit form.
+    ///
+    /// Requires `opentelemetry_span_log` to be enabled in the engine configuration.
+    pub fn engine_spans(&self, trace_id: &str) -> Result<Vec<EngineSpan>, Error> {
+        let rows = self.query_rows(&format!(
+            "SELECT span_id, parent_span_id, operation_name, start_time_us, finish_time_us \
+             FROM system.opentelemetry_span_log \
+             WHERE replaceAll(toString(trace_id), '-', '') = lower({}) ORDER BY start_time_us",
+            quote_string(trace_id)
+        ))?;
+
+        rows.into_iter()
+            .map(|row| match <[String; 5]>::try_from(row) {
+                Ok([span_id, parent_span_id, operation_name, start, finish]) => Ok(EngineSpan {
+                    span_id: parse_u64(&span_id)?,
+                    parent_span_id: parse_u64(&parent_span_id)?,
+                    operation_name,
+                    start_time_us: parse_u64(&start)?,
+                    finish_time_us: parse_u64(&finish)?,
+                }),
+                Err(row) => Err(unexpected_columns(5, row.len())),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::Error;
+use crate::remote::RemoteConfig;
+use crate::session::Session;
+use crate::settings::setting;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::sync::count_where;
+use crate::sync::push_rows;
+use crate::sync::PushBatch;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Ships rows buffered in a local table upstream, each batch exactly once.
+///
+/// Batches are recorded in a control table ("<table>_outbox") before they are sent and
+/// uploaded with the batch id as `insert_deduplication_token`. A batch interrupted by a
+/// crash is resent with the same token on the next `ship`, so the upstream table must
+/// deduplicate inserts: a Replicated table, or a MergeTree with
+/// `non_replicated_deduplication_window` set. The ordering column must be strictly increasing
+/// for resent batches to hold the same rows.
+pub struct Outbox<'a> {
+    session: &'a Session,
+    table: String,
+    control_table: String,
+    remote: RemoteConfig,
+    batch: PushBatch,
+}
+
+/// Batch uploaded by `Outbox::ship`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShippedBatch {
+    pub batch_id: String,
+    pub rows: u64,
+}
+
+impl<'a> Outbox<'a> {
+    /// Creates the control table if it doesn't exist.
+    pub fn open(
+        session: &'a Session,
+        table: &str,
+        remote: RemoteConfig,
+        batch: PushBatch,
+    ) -> Result<Self, Error> {
+        let control_table = format!("{}_outbox", table);
+        session.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} \
+                 (batch_id String, cutoff String, rows UInt64, shipped UInt8, version UInt64) \
+                 ENGINE = ReplacingMergeTree(version) ORDER BY batch_id",
+                quote_qualified(&control_table)
+            ),
+            None,
+        )?;
+
+        Ok(Self {
+            session,
+            table: table.to_string(),
+            control_table,
+            remote,
+            batch,
+        })
+    }
+
+    /// Ships the pending batch left by an interrupted call, or else the next batch of rows.
+    /// Returns `None` when there is nothing to ship.
+    pub fn ship(&self) -> Result<Option<ShippedBatch>, Error> {
+        let (batch_id, cutoff, rows) = match self.pending()? {
+            Some(pending) => pending,
+            None => {
+                let Some(cutoff) = self.batch.next_cutoff(self.session, &self.table)? else {
+                    return Ok(None);
+                };
+                let rows = count_where(self.session, &self.table, &self.batch.filter(&cutoff))?;
+                let batch_id = self.record(&cutoff, rows)?;
+                (batch_id, cutoff, rows)
+            }
+        };
+
+        let filter = self.batch.filter(&cutoff);
+        push_rows(
+            self.session,
+            &self.table,
+            &self.remote,
+            &self.batch,
+            &filter,
+            Some(&[setting("insert_deduplication_token", &batch_id)]),
+        )?;
+        self.session.execute(
+            &format!(
+                "DELETE FROM {} WHERE {}",
+                quote_qualified(&self.table),
+                filter
+            ),
+            None,
+        )?;
+        self.mark_shipped(&batch_id, &cutoff, rows)?;
+
+        Ok(Some(ShippedBatch { batch_id, rows }))
+    }
+
+    /// Ships batches until the table is empty, returns them in order.
+    pub fn drain(&self) -> Result<Vec<ShippedBatch>, Error> {
+        let mut shipped = Vec::new();
+        while let Some(batch) = self.ship()? {
+            shipped.push(batch);
+        }
+        Ok(shipped)
+    }
+
+    pub fn is_shipped(&self, batch_id: &str) -> Result<bool, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT count() FROM {} FINAL WHERE batch_id = {} AND shipped = 1",
+            quote_qualified(&self.control_table),
+            quote_string(batch_id)
+        ))?;
+
+        Ok(rows.first().and_then(|row| row.first()).map(String::as_str) == Some("1"))
+    }
+
+    fn pending(&self) -> Result<Option<(String, String, u64)>, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT batch_id, cutoff, rows FROM {} FINAL WHERE shipped = 0 ORDER BY version LIMIT 1",
+            quote_qualified(&self.control_table)
+        ))?;
+
+        match rows.into_iter().next() {
+            Some(row) => {
+                let [batch_id, cutoff, rows] =
+                    <[String; 3]>::try_from(row).map_err(|row| unexpected_columns(3, row.len()))?;
+                Ok(Some((batch_id, cutoff, parse_u64(&rows)?)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Records a pending batch, returns its id.
+    fn record(&self, cutoff: &str, rows: u64) -> Result<String, Error> {
+        let ids = self
+            .session
+            .query_rows("SELECT toString(generateUUIDv4())")?;
+        let batch_id = ids
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or_else(|| Error::InvalidData("empty generateUUIDv4() result".to_string()))?;
+
+        self.write(&batch_id, cutoff, rows, false)?;
+        Ok(batch_id)
+    }
+
+    fn mark_shipped(&self, batch_id: &str, cutoff: &str, rows: u64) -> Result<(), Error> {
+        self.write(batch_id, cutoff, rows, true)
+    }
+
+    fn write(&self, batch_id: &str, cutoff: &str, rows: u64, shipped: bool) -> Result<(), Error> {
+        self.session.execute(
+            &format!(
+                "INSERT INTO {} SELECT {}, {}, {}, {}, toUnixTimestamp64Nano(now64(9))",
+                quote_qualified(&self.control_table),
+                quote_string(batch_id),
+                quote_string(cutoff),
+                rows,
+                shipped as u8
+            ),
+            None,
+        )?;
+
+        Ok(())
+    }
+}
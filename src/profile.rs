@@ -0,0 +1,139 @@
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::format::OutputFormat;
+use crate::sql::quote_identifier;
+use crate::table::Table;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Most frequent values reported per column.
+const TOP_K: usize = 5;
+
+/// Type prefixes without a meaningful ordering, no min/max is computed for them.
+const UNORDERED_TYPES: &[&str] = &[
+    "AggregateFunction",
+    "Array",
+    "Dynamic",
+    "JSON",
+    "Map",
+    "Object",
+    "Tuple",
+    "Variant",
+];
+
+/// Column statistics over a sample of a table, see `Table::profile`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableProfile {
+    pub sample_fraction: f64,
+    pub rows_sampled: u64,
+    pub columns: Vec<ColumnProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub type_name: String,
+    /// `None` for unordered types and columns without non-NULL values.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Share of NULLs in the sample, 0 to 1.
+    pub null_ratio: f64,
+    /// Approximate distinct values in the sample, a lower bound for the whole table.
+    pub distinct_estimate: u64,
+    /// Most frequent values in the sample, most frequent first.
+    pub top_values: Vec<String>,
+}
+
+impl<'a> Table<'a> {
+    /// Profiles the columns over a random sample of about `sample_fraction` of the rows, all
+    /// rows when it is 1 or more.
+    pub fn profile(&self, sample_fraction: f64) -> Result<TableProfile, Error> {
+        let schema = self.schema()?;
+        let filter = if sample_fraction < 1.0 {
+            format!(" WHERE randCanonical() < {}", sample_fraction.max(0.0))
+        } else {
+            String::new()
+        };
+        // a single scan, so all statistics describe the same sample
+        let mut select = vec!["count()".to_string()];
+        for column in &schema.columns {
+            let name = quote_identifier(&column.name);
+            select.push(format!("countIf(isNull({}))", name));
+            select.push(format!("uniq({})", name));
+            if is_ordered(&column.type_name) {
+                select.push(format!("toString(min({}))", name));
+                select.push(format!("toString(max({}))", name));
+            } else {
+                select.push("NULL".to_string());
+                select.push("NULL".to_string());
+            }
+            select.push(format!(
+                "arrayStringConcat(arrayMap(x -> toString(x), topK({})({})), '\\0')",
+                TOP_K, name
+            ));
+        }
+
+        let result = self.session().execute(
+            &format!(
+                "SELECT {} FROM {}{}",
+                select.join(", "),
+                self.quoted_name(),
+                filter
+            ),
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )?;
+        let row = result
+            .tsv_rows(None)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if row.len() != select.len() {
+            return Err(unexpected_columns(select.len(), row.len()));
+        }
+
+        let mut fields = row.into_iter();
+        let rows_sampled = parse_u64(&fields.next().flatten().unwrap_or_default())?;
+        let mut columns = Vec::with_capacity(schema.columns.len());
+        for column in schema.columns {
+            let mut next = || fields.next().flatten();
+            let (nulls, distinct, min, max, top) = (next(), next(), next(), next(), next());
+            let nulls = nulls.unwrap_or_default();
+            let distinct = distinct.unwrap_or_default();
+            let top = top.unwrap_or_default();
+
+            columns.push(ColumnProfile {
+                name: column.name,
+                type_name: column.type_name,
+                min,
+                max,
+                null_ratio: if rows_sampled == 0 {
+                    0.0
+                } else {
+                    parse_u64(&nulls)? as f64 / rows_sampled as f64
+                },
+                distinct_estimate: parse_u64(&distinct)?,
+                top_values: if top.is_empty() {
+                    Vec::new()
+                } else {
+                    top.split('\0').map(str::to_string).collect()
+                },
+            });
+        }
+
+        Ok(TableProfile {
+            sample_fraction: sample_fraction.clamp(0.0, 1.0),
+            rows_sampled,
+            columns,
+        })
+    }
+}
+
+fn is_ordered(type_name: &str) -> bool {
+    let mut inner = type_name;
+    for wrapper in ["Nullable(", "LowCardinality("] {
+        inner = inner.strip_prefix(wrapper).unwrap_or(inner);
+    }
+    !UNORDERED_TYPES
+        .iter()
+        .any(|prefix| inner.starts_with(prefix))
+}
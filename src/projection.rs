@@ -0,0 +1,60 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+use crate::table::Table;
+
+impl<'a> Table<'a> {
+    /// Adds a projection, `query` is its body, e.g. "SELECT * ORDER BY user_id".
+    pub fn add_projection(&self, name: &str, query: &str) -> Result<(), Error> {
+        self.alter(&format!(
+            "ADD PROJECTION IF NOT EXISTS {} ({})",
+            quote_identifier(name),
+            query
+        ))
+    }
+
+    /// Builds the projection for parts written before it was added.
+    pub fn materialize_projection(&self, name: &str) -> Result<(), Error> {
+        self.alter(&format!(
+            "MATERIALIZE PROJECTION {}",
+            quote_identifier(name)
+        ))
+    }
+
+    pub fn drop_projection(&self, name: &str) -> Result<(), Error> {
+        self.alter(&format!(
+            "DROP PROJECTION IF EXISTS {}",
+            quote_identifier(name)
+        ))
+    }
+}
+
+impl Session {
+    /// Checks the query plan of `query` for a read from projection `name`.
+    pub fn uses_projection(&self, query: &str, name: &str) -> Result<bool, Error> {
+        let plan = match self.query_rows(&format!("EXPLAIN projections = 1 {}", query)) {
+            Ok(plan) => plan,
+            // Engines without the `projections` EXPLAIN setting still name the projection in the plan.
+            Err(Error::QueryError(message)) if is_unknown_projections_setting(&message) => {
+                self.query_rows(&format!("EXPLAIN {}", query))?
+            }
+            Err(e) => return Err(e),
+        };
+
+        let markers = [
+            format!("ReadFromMergeTree ({})", name),
+            format!("projection {})", name),
+        ];
+
+        Ok(plan.iter().flatten().any(|line| {
+            let line = line.trim();
+            line.strip_prefix("Name: ") == Some(name)
+                || markers.iter().any(|marker| line.contains(marker.as_str()))
+        }))
+    }
+}
+
+fn is_unknown_projections_setting(message: &str) -> bool {
+    message.contains("projections")
+        && (message.contains("UNKNOWN_SETTING") || message.contains("Unknown setting"))
+}
@@ -0,0 +1,347 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::error::Error;
+use crate::format::InputFormat;
+use crate::schema::Column;
+use crate::schema::Schema;
+use crate::settings::ExecuteOptions;
+use crate::sql::accepts_ctes;
+use crate::sql::quote_identifier;
+use crate::sql::quote_string;
+
+/// Larger `IdSet`s go through an external table when executed with `Session::execute_query`.
+const INLINE_ID_LIMIT: usize = 10_000;
+
+/// Value bound to a template placeholder.
+#[derive(Debug, Clone)]
+pub enum Param<'a> {
+    Identifier(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Timestamp(SystemTime),
+    /// Ids for `x IN {name:IdSet}`.
+    IdSet(Cow<'a, [u64]>),
+}
+
+/// SQL text with typed `{name:Type}` placeholders, rendered client-side.
+///
+/// `Identifier` placeholders are backtick-quoted, numeric types are range checked and every
+/// other type is rendered as `CAST(<string literal> AS <Type>)`. Placeholders inside string
+/// literals are left alone.
+///
+/// `{name:IdSet}` placeholders take a `Param::IdSet` and render as a parenthesized list, or
+/// as a subquery over an external table for large sets, see `Session::execute_query`.
+#[derive(Debug, Clone)]
+pub struct Query<'a> {
+    parts: Vec<Part>,
+    params: HashMap<String, Param<'a>>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Placeholder { name: String, kind: String },
+}
+
+impl<'a> Query<'a> {
+    pub fn template(template: &str) -> Result<Self, Error> {
+        Ok(Self {
+            parts: parse(template)?,
+            params: HashMap::new(),
+        })
+    }
+
+    pub fn bind(mut self, name: &str, value: impl Into<Param<'a>>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Renders the whole query as SQL text, inlining every `IdSet`.
+    pub fn render(&self) -> Result<String, Error> {
+        self.render_parts(None)
+    }
+
+    /// Renders the query for execution with `options`, moving large `IdSet`s into external
+    /// tables of the returned options. Statements without a SELECT part, such as `ALTER` or
+    /// `DELETE`, can't read external tables and get every `IdSet` inlined.
+    pub fn render_with_options<'b>(
+        &self,
+        options: &ExecuteOptions<'b>,
+    ) -> Result<(String, ExecuteOptions<'b>), Error> {
+        // placeholders don't change the statement kind
+        let text = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(text.as_str()),
+                Part::Placeholder { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !accepts_ctes(&text) {
+            return Ok((self.render()?, options.clone()));
+        }
+
+        let mut options = options.clone();
+        let sql = self.render_parts(Some(&mut options))?;
+        Ok((sql, options))
+    }
+
+    fn render_parts(&self, mut options: Option<&mut ExecuteOptions>) -> Result<String, Error> {
+        let mut sql = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => sql.push_str(text),
+                Part::Placeholder { name, kind } => {
+                    let value = self.params.get(name).ok_or_else(|| {
+                        Error::TemplateError(format!("no value bound for {{{}:{}}}", name, kind))
+                    })?;
+                    match (kind.as_str(), value) {
+                        ("IdSet", Param::IdSet(ids)) => {
+                            sql.push_str(&render_id_set(name, ids, options.as_deref_mut()))
+                        }
+                        _ => sql.push_str(&render_param(name, kind, value)?),
+                    }
+                }
+            }
+        }
+
+        Ok(sql)
+    }
+}
+
+impl<'a> From<&'a str> for Param<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for Param<'_> {
+    fn from(value: String) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<i64> for Param<'_> {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u64> for Param<'_> {
+    fn from(value: u64) -> Self {
+        Self::UInt(value)
+    }
+}
+
+impl From<f64> for Param<'_> {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for Param<'_> {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<SystemTime> for Param<'_> {
+    fn from(value: SystemTime) -> Self {
+        Self::Timestamp(value)
+    }
+}
+
+impl<'a> From<&'a [u64]> for Param<'a> {
+    fn from(value: &'a [u64]) -> Self {
+        Self::IdSet(value.into())
+    }
+}
+
+impl From<Vec<u64>> for Param<'_> {
+    fn from(value: Vec<u64>) -> Self {
+        Self::IdSet(value.into())
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Part>, Error> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.char_indices();
+    let mut quote: Option<char> = None;
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(q) = quote {
+            text.push(c);
+            if c == '\\' {
+                if let Some((_, escaped)) = chars.next() {
+                    text.push(escaped);
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '"' | '`' => {
+                quote = Some(c);
+                text.push(c);
+            }
+            '{' => match placeholder(&template[i + 1..]) {
+                Some((name, kind, len)) => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder {
+                        name: name.to_string(),
+                        kind: kind.trim().to_string(),
+                    });
+                    for _ in 0..len {
+                        chars.next();
+                    }
+                }
+                None => text.push(c),
+            },
+            c => text.push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Error::TemplateError("unterminated quote".to_string()));
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    Ok(parts)
+}
+
+/// Matches `name:Type}` at the start of `rest`, returning the char length consumed.
+fn placeholder(rest: &str) -> Option<(&str, &str, usize)> {
+    let end = rest.find('}')?;
+    let body = &rest[..end];
+    if body.contains('{') {
+        return None;
+    }
+
+    let (name, kind) = body.split_once(':')?;
+    let mut name_chars = name.chars();
+    let valid_name = name_chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name_chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name || kind.trim().is_empty() {
+        return None;
+    }
+
+    Some((name, kind, body.chars().count() + 1))
+}
+
+fn render_param(name: &str, kind: &str, value: &Param) -> Result<String, Error> {
+    let mismatch = || {
+        Err(Error::TemplateError(format!(
+            "{:?} can't be bound to {{{}:{}}}",
+            value, name, kind
+        )))
+    };
+
+    if kind == "Identifier" {
+        return match value {
+            Param::Identifier(v) | Param::String(v) => Ok(quote_identifier(v)),
+            _ => mismatch(),
+        };
+    }
+
+    if let Some((min, max)) = integer_range(kind) {
+        let value = match value {
+            Param::Int(v) => *v as i128,
+            Param::UInt(v) => *v as i128,
+            _ => return mismatch(),
+        };
+        if value < min || value > max {
+            return Err(Error::TemplateError(format!(
+                "{} is out of range for {{{}:{}}}",
+                value, name, kind
+            )));
+        }
+        return Ok(value.to_string());
+    }
+
+    match (kind, value) {
+        ("String", Param::String(v)) => Ok(quote_string(v)),
+        ("Float32" | "Float64", Param::Float(v)) => Ok(v.to_string()),
+        ("Float32" | "Float64", Param::Int(v)) => Ok(v.to_string()),
+        ("Float32" | "Float64", Param::UInt(v)) => Ok(v.to_string()),
+        ("Bool", Param::Bool(v)) => Ok(v.to_string()),
+        ("DateTime" | "Date", Param::Timestamp(v)) => {
+            Ok(format!("CAST(toDateTime({}) AS {})", unix_secs(*v), kind))
+        }
+        (_, Param::String(v)) if !matches!(kind, "Float32" | "Float64" | "Bool") => {
+            Ok(format!("CAST({} AS {})", quote_string(v), kind))
+        }
+        _ => mismatch(),
+    }
+}
+
+fn render_id_set(name: &str, ids: &[u64], options: Option<&mut ExecuteOptions>) -> String {
+    if ids.is_empty() {
+        return "(SELECT toUInt64(0) WHERE 0)".to_string();
+    }
+
+    match options {
+        Some(options) if ids.len() > INLINE_ID_LIMIT => {
+            let table = format!("_chdb_ids_{}", name);
+            let mut data = String::with_capacity(ids.len() * 8);
+            for id in ids {
+                data.push_str(&id.to_string());
+                data.push('\n');
+            }
+            let schema = Schema {
+                columns: vec![Column {
+                    name: "id".to_string(),
+                    type_name: "UInt64".to_string(),
+                }],
+            };
+
+            *options = std::mem::take(options).with_external_table(
+                &table,
+                &schema,
+                data,
+                InputFormat::TabSeparated,
+            );
+            format!("(SELECT id FROM {})", quote_identifier(&table))
+        }
+        _ => {
+            let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+            format!("({})", ids.join(", "))
+        }
+    }
+}
+
+fn integer_range(kind: &str) -> Option<(i128, i128)> {
+    Some(match kind {
+        "UInt8" => (0, u8::MAX as i128),
+        "UInt16" => (0, u16::MAX as i128),
+        "UInt32" => (0, u32::MAX as i128),
+        "UInt64" | "UInt128" | "UInt256" => (0, i128::MAX),
+        "Int8" => (i8::MIN as i128, i8::MAX as i128),
+        "Int16" => (i16::MIN as i128, i16::MAX as i128),
+        "Int32" => (i32::MIN as i128, i32::MAX as i128),
+        "Int64" | "Int128" | "Int256" => (i128::MIN, i128::MAX),
+        _ => return None,
+    })
+}
+
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_string;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Finished (or failed) query from `system.query_log`.
+#[derive(Debug, Clone)]
+pub struct QueryLogEntry {
+    pub query: String,
+    pub event_time: String,
+    pub duration: Duration,
+    pub read_rows: u64,
+    pub read_bytes: u64,
+    pub result_rows: u64,
+    pub memory_usage: u64,
+    /// Empty for successful queries.
+    pub exception: String,
+}
+
+impl Session {
+    /// Queries executed with `ExecuteOptions::comment(comment)`, oldest first.
+    ///
+    /// chDB keeps no query log by default. It needs the `query_log` section in the engine
+    /// configuration and the `log_queries` setting, otherwise this fails with
+    /// `Error::QueryLogUnavailable`:
+    ///
+    /// ```text
+    /// SessionBuilder::new()
+    ///     .with_config(EngineConfig::new().with_value("query_log.database", "system")
+    ///         .with_value("query_log.table", "query_log"))
+    ///     .with_arg(Arg::Custom("log_queries".into(), Some("1".into())))
+    /// ```
+    pub fn queries_by_comment(&self, comment: &str) -> Result<Vec<QueryLogEntry>, Error> {
+        self.flush_query_log()?;
+
+        let rows = self.query_rows(&format!(
+            "SELECT query, toString(event_time), query_duration_ms, read_rows, read_bytes, result_rows, memory_usage, exception \
+             FROM system.query_log WHERE type != 'QueryStart' AND log_comment = {} \
+             ORDER BY event_time_microseconds",
+            quote_string(comment)
+        ))?;
+
+        rows.into_iter()
+            .map(|row| match <[String; 8]>::try_from(row) {
+                Ok(
+                    [query, event_time, duration_ms, read_rows, read_bytes, result_rows, memory_usage, exception],
+                ) => Ok(QueryLogEntry {
+                    query,
+                    event_time,
+                    duration: Duration::from_millis(parse_u64(&duration_ms)?),
+                    read_rows: parse_u64(&read_rows)?,
+                    read_bytes: parse_u64(&read_bytes)?,
+                    result_rows: parse_u64(&result_rows)?,
+                    memory_usage: parse_u64(&memory_usage)?,
+                    exception,
+                }),
+                Err(row) => Err(unexpected_columns(8, row.len())),
+            })
+            .collect()
+    }
+
+    /// Writes pending entries to `system.query_log`, failing with
+    /// `Error::QueryLogUnavailable` unless queries are logged there.
+    pub(crate) fn flush_query_log(&self) -> Result<(), Error> {
+        self.execute("SYSTEM FLUSH LOGS", None)?;
+
+        let rows = self.query_rows(
+            "SELECT getSetting('log_queries'), \
+             (SELECT count() FROM system.tables WHERE database = 'system' AND name = 'query_log')",
+        )?;
+        let logged = rows
+            .first()
+            .is_some_and(|row| row.iter().all(|value| value != "0"));
+        if !logged {
+            return Err(Error::QueryLogUnavailable);
+        }
+
+        Ok(())
+    }
+}
@@ -3,18 +3,108 @@ use std::borrow::Cow;
 use std::ffi::CStr;
 use std::time::Duration;
 
+use sha2::Digest;
+use sha2::Sha256;
+
 use crate::bindings;
 use crate::error::Error;
+use crate::tsv;
+
+/// Engine or owned output, with the NULL representation it was produced with if not "\\N".
+pub struct QueryResult(Inner, Option<String>);
 
-#[derive(Clone)]
-pub struct QueryResult(pub(crate) *mut bindings::local_result_v2);
+enum Inner {
+    Engine(*mut bindings::local_result_v2),
+    Owned { data: Vec<u8>, stats: QueryStats },
+}
+
+/// Statistics reported with a result.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueryStats {
+    pub rows_read: u64,
+    pub bytes_read: u64,
+    pub elapsed: Duration,
+}
+
+/// Rows returned against rows the query would have returned without its `LIMIT`, see
+/// `QueryResult::truncated_info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedInfo {
+    pub rows: u64,
+    /// Lower bound, the engine may stop counting early.
+    pub rows_before_limit_at_least: u64,
+}
+
+impl TruncatedInfo {
+    /// Whether the `LIMIT` cut rows off, as in "showing first `rows` of at least ...".
+    pub fn is_truncated(&self) -> bool {
+        self.rows_before_limit_at_least > self.rows
+    }
+}
 
 impl QueryResult {
+    pub(crate) fn from_engine(result: *mut bindings::local_result_v2) -> Self {
+        Self(Inner::Engine(result), None)
+    }
+
+    /// Result without output, not backed by the engine.
+    pub fn empty() -> Self {
+        Self::from_owned(Vec::new(), QueryStats::default())
+    }
+
+    /// Whether the result has no output, as for DDL statements and inserts.
+    pub fn is_empty(&self) -> bool {
+        self.data_ref().is_empty()
+    }
+
+    pub(crate) fn from_owned(data: Vec<u8>, stats: QueryStats) -> Self {
+        Self(Inner::Owned { data, stats }, None)
+    }
+
+    /// Result with the statistics of `self` and `data` as output, e.g. for `ResultTransform`s.
+    pub fn with_data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        let stats = self.stats();
+        let null_as = self.1.take();
+        Self(
+            Inner::Owned {
+                data: data.into(),
+                stats,
+            },
+            null_as,
+        )
+    }
+
+    /// Records the NULL representation the output was produced with, see `tsv_rows`.
+    pub(crate) fn with_null_as(mut self, null_as: Option<&str>) -> Self {
+        if let Some(null_as) = null_as {
+            self.1 = Some(null_as.to_string());
+        }
+        self
+    }
+
+    /// Result holding `data` without running the engine, for tests of code consuming results.
+    #[cfg(feature = "test-utils")]
+    pub fn from_bytes_for_tests(data: impl Into<Vec<u8>>, stats: QueryStats) -> Self {
+        Self::from_owned(data.into(), stats)
+    }
+
     pub fn data_utf8(&self) -> Result<String, Error> {
         String::from_utf8(self.data_ref().to_vec())
             .map_err(|e| Error::NonUtf8Sequence(e.to_string()))
     }
 
+    /// Takes the output as a `String`. Owned results are moved without copying, engine
+    /// buffers are copied once after validation.
+    pub fn into_string(mut self) -> Result<String, Error> {
+        match &mut self.0 {
+            Inner::Owned { data, .. } => String::from_utf8(std::mem::take(data))
+                .map_err(|e| Error::NonUtf8Sequence(e.to_string())),
+            Inner::Engine(_) => std::str::from_utf8(self.data_ref())
+                .map(str::to_owned)
+                .map_err(|e| Error::NonUtf8Sequence(e.to_string())),
+        }
+    }
+
     pub fn data_utf8_lossy<'a>(&'a self) -> Cow<'a, str> {
         String::from_utf8_lossy(self.data_ref())
     }
@@ -24,27 +114,75 @@ impl QueryResult {
     }
 
     pub fn data_ref(&self) -> &[u8] {
-        let buf = unsafe { (*self.0).buf };
-        let len = unsafe { (*self.0).len };
-        let bytes: &[u8] = unsafe { slice::from_raw_parts(buf as *const u8, len) };
-        bytes
+        match &self.0 {
+            Inner::Engine(result) => {
+                let buf = unsafe { (**result).buf };
+                let len = unsafe { (**result).len };
+                if buf.is_null() {
+                    return &[];
+                }
+                let bytes: &[u8] = unsafe { slice::from_raw_parts(buf as *const u8, len) };
+                bytes
+            }
+            Inner::Owned { data, .. } => data,
+        }
+    }
+
+    /// Decodes a TabSeparated payload, mapping fields equal to `null_as` to `None`.
+    ///
+    /// `None` uses the representation the query was run with: the one set by
+    /// `ExecuteOptions::null_as` for `Session::execute_with`, "\\N" otherwise.
+    pub fn tsv_rows(&self, null_as: Option<&str>) -> Result<Vec<Vec<Option<String>>>, Error> {
+        let null_as = null_as.or(self.1.as_deref()).unwrap_or("\\N");
+        tsv::parse_nullable(self.data_ref(), null_as.as_bytes())
+    }
+
+    /// `LIMIT` counts of a `JSON` or `JSONCompact` result, `None` for other formats and
+    /// for queries without `LIMIT`.
+    pub fn truncated_info(&self) -> Option<TruncatedInfo> {
+        let data = std::str::from_utf8(self.data_ref()).ok()?;
+        Some(TruncatedInfo {
+            rows: json_statistic(data, "rows")?,
+            rows_before_limit_at_least: json_statistic(data, "rows_before_limit_at_least")?,
+        })
+    }
+
+    /// SHA-256 of the output buffer, to compare results of exports and replications.
+    pub fn digest(&self) -> [u8; 32] {
+        Sha256::digest(self.data_ref()).into()
     }
 
     pub fn rows_read(&self) -> u64 {
-        (unsafe { *self.0 }).rows_read
+        self.stats().rows_read
     }
 
     pub fn bytes_read(&self) -> u64 {
-        unsafe { (*self.0).bytes_read }
+        self.stats().bytes_read
     }
 
     pub fn elapsed(&self) -> Duration {
-        let elapsed = unsafe { (*self.0).elapsed };
-        Duration::from_secs_f64(elapsed)
+        self.stats().elapsed
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        match &self.0 {
+            Inner::Engine(result) => {
+                let result = unsafe { **result };
+                QueryStats {
+                    rows_read: result.rows_read,
+                    bytes_read: result.bytes_read,
+                    elapsed: Duration::from_secs_f64(result.elapsed),
+                }
+            }
+            Inner::Owned { stats, .. } => *stats,
+        }
     }
 
     pub(crate) fn check_error(self) -> Result<Self, Error> {
-        let err_ptr = unsafe { (*self.0).error_message };
+        let Inner::Engine(result) = self.0 else {
+            return Ok(self);
+        };
+        let err_ptr = unsafe { (*result).error_message };
 
         if err_ptr.is_null() {
             return Ok(self);
@@ -56,8 +194,115 @@ impl QueryResult {
     }
 }
 
+/// Minimum and maximum row of a query run with `extremes = 1`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extremes {
+    pub min: serde_json::Value,
+    pub max: serde_json::Value,
+}
+
+#[cfg(feature = "serde")]
+impl QueryResult {
+    /// `WITH TOTALS` row of a `JSON` or `JSONCompact` result, `None` if the query had none.
+    pub fn totals(&self) -> Result<Option<serde_json::Value>, Error> {
+        Ok(self.json_document()?.remove("totals"))
+    }
+
+    /// Extremes of a `JSON` or `JSONCompact` result, `None` unless run with `extremes = 1`.
+    pub fn extremes(&self) -> Result<Option<Extremes>, Error> {
+        let Some(serde_json::Value::Object(mut extremes)) =
+            self.json_document()?.remove("extremes")
+        else {
+            return Ok(None);
+        };
+        match (extremes.remove("min"), extremes.remove("max")) {
+            (Some(min), Some(max)) => Ok(Some(Extremes { min, max })),
+            _ => Err(Error::InvalidData(
+                "extremes without min and max".to_string(),
+            )),
+        }
+    }
+
+    fn json_document(&self) -> Result<serde_json::Map<String, serde_json::Value>, Error> {
+        serde_json::from_slice(self.data_ref()).map_err(|e| Error::InvalidData(e.to_string()))
+    }
+}
+
+/// Owned copy of a result's payload and statistics.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QueryReport {
+    pub data: String,
+    pub rows_read: u64,
+    pub bytes_read: u64,
+    /// Serialized as fractional seconds.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_secs"))]
+    pub elapsed: Duration,
+    pub query_id: Option<String>,
+}
+
+impl QueryReport {
+    pub fn new(result: &QueryResult) -> Self {
+        Self {
+            data: result.data_utf8_lossy().into_owned(),
+            rows_read: result.rows_read(),
+            bytes_read: result.bytes_read(),
+            elapsed: result.elapsed(),
+            query_id: None,
+        }
+    }
+
+    /// The engine does not report query ids, set it when one was passed with `Arg::Custom("query_id", ..)`.
+    pub fn with_query_id(mut self, query_id: impl Into<String>) -> Self {
+        self.query_id = Some(query_id.into());
+        self
+    }
+}
+
+impl From<&QueryResult> for QueryReport {
+    fn from(result: &QueryResult) -> Self {
+        Self::new(result)
+    }
+}
+
+/// Top-level counter `key` of JSON output; only top-level keys are indented by a single tab.
+fn json_statistic(data: &str, key: &str) -> Option<u64> {
+    let pattern = format!("\n\t\"{}\":", key);
+    let start = data.rfind(&pattern)? + pattern.len();
+    let value = data[start..].split([',', '\n']).next()?;
+    value.trim().trim_matches('"').parse().ok()
+}
+
+#[cfg(feature = "serde")]
+fn serialize_secs<S: serde::Serializer>(
+    value: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(value.as_secs_f64())
+}
+
+// SAFETY: the engine result is a heap allocation owned by this value alone (clones copy the
+// buffer), it is only read through `&self` and `free_result_v2` frees it from any thread.
+unsafe impl Send for QueryResult {}
+
+/// Clones of engine results own a copy of the buffer, the engine result is freed only once.
+impl Clone for QueryResult {
+    fn clone(&self) -> Self {
+        Self(
+            Inner::Owned {
+                data: self.data_ref().to_vec(),
+                stats: self.stats(),
+            },
+            self.1.clone(),
+        )
+    }
+}
+
 impl Drop for QueryResult {
     fn drop(&mut self) {
-        unsafe { bindings::free_result_v2(self.0) };
+        if let Inner::Engine(result) = self.0 {
+            unsafe { bindings::free_result_v2(result) };
+        }
     }
 }
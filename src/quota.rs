@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Client-side per-caller budgets, checked before a query is sent to the engine.
+///
+/// Budgets are sliding one minute windows. The bytes budget is charged after a query
+/// finishes, so a single query may overshoot it; the next one is rejected.
+#[derive(Debug, Default)]
+pub struct QuotaManager {
+    queries_per_minute: Option<u64>,
+    bytes_read_per_minute: Option<u64>,
+    callers: Mutex<HashMap<String, VecDeque<Charge>>>,
+    next_charge: AtomicU64,
+}
+
+/// Usage of a caller within the current window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub queries: u64,
+    pub bytes_read: u64,
+}
+
+#[derive(Debug)]
+struct Charge {
+    id: u64,
+    at: Instant,
+    bytes_read: u64,
+}
+
+impl QuotaManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_queries_per_minute(mut self, limit: u64) -> Self {
+        self.queries_per_minute = Some(limit);
+        self
+    }
+
+    pub fn with_bytes_read_per_minute(mut self, limit: u64) -> Self {
+        self.bytes_read_per_minute = Some(limit);
+        self
+    }
+
+    /// Runs `query` on `session` on behalf of `caller`, or fails with `Error::QuotaExceeded`.
+    pub fn execute(
+        &self,
+        session: &Session,
+        caller: &str,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        let charge = self.acquire(caller)?;
+
+        let result = session.execute(query, query_args);
+        if let Ok(result) = &result {
+            self.charge_bytes(caller, charge, result.bytes_read());
+        }
+
+        result
+    }
+
+    pub fn usage(&self, caller: &str) -> QuotaUsage {
+        let mut callers = self.callers.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(charges) = callers.get_mut(caller) else {
+            return QuotaUsage::default();
+        };
+
+        expire(charges, Instant::now());
+        QuotaUsage {
+            queries: charges.len() as u64,
+            bytes_read: charges.iter().map(|c| c.bytes_read).sum(),
+        }
+    }
+
+    /// Forgets all usage of `caller`.
+    pub fn reset(&self, caller: &str) {
+        self.callers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(caller);
+    }
+
+    /// Records a query of `caller` and returns the id of its charge.
+    fn acquire(&self, caller: &str) -> Result<u64, Error> {
+        let now = Instant::now();
+        let mut callers = self.callers.lock().unwrap_or_else(|e| e.into_inner());
+        let charges = callers.entry(caller.to_string()).or_default();
+        expire(charges, now);
+
+        if let Some(limit) = self.queries_per_minute {
+            if charges.len() as u64 >= limit {
+                return Err(Error::QuotaExceeded(format!(
+                    "{} reached {} queries per minute",
+                    caller, limit
+                )));
+            }
+        }
+
+        if let Some(limit) = self.bytes_read_per_minute {
+            let used: u64 = charges.iter().map(|c| c.bytes_read).sum();
+            if used >= limit {
+                return Err(Error::QuotaExceeded(format!(
+                    "{} read {} of {} bytes per minute",
+                    caller, used, limit
+                )));
+            }
+        }
+
+        let id = self.next_charge.fetch_add(1, Ordering::Relaxed);
+        charges.push_back(Charge {
+            id,
+            at: now,
+            bytes_read: 0,
+        });
+
+        Ok(id)
+    }
+
+    /// Adds `bytes_read` to charge `id`, which is gone if its window already expired.
+    fn charge_bytes(&self, caller: &str, id: u64, bytes_read: u64) {
+        let mut callers = self.callers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(charge) = callers
+            .get_mut(caller)
+            .and_then(|c| c.iter_mut().find(|c| c.id == id))
+        {
+            charge.bytes_read += bytes_read;
+        }
+    }
+}
+
+fn expire(charges: &mut VecDeque<Charge>, now: Instant) {
+    while charges
+        .front()
+        .is_some_and(|c| now.duration_since(c.at) >= WINDOW)
+    {
+        charges.pop_front();
+    }
+}
@@ -0,0 +1,54 @@
+//! Serde helpers for numbers that JSON output writes as strings.
+//!
+//! Use with `#[serde(with = "chdb_rust::quoted")]` on fields of any type implementing
+//! `FromStr` and `Display`, e.g. `i128`, `u64` or a decimal type. `(U)Int256` values have no
+//! primitive counterpart; use `String` or a big integer type for them.
+
+use std::fmt;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de;
+use serde::Deserializer;
+use serde::Serializer;
+
+pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Accepts quoted values as well as plain JSON integers.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(QuotedVisitor(PhantomData))
+}
+
+struct QuotedVisitor<T>(PhantomData<T>);
+
+impl<T> de::Visitor<'_> for QuotedVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number or a quoted number")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        self.visit_str(&value.to_string())
+    }
+}
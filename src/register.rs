@@ -0,0 +1,48 @@
+use std::io::BufWriter;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::table::Table;
+use crate::temp::TempFile;
+
+impl Session {
+    /// Materializes `rows` as table `name` so they can be joined against other tables.
+    ///
+    /// Every row must serialize to a JSON object, column types are inferred from the values.
+    /// The table replaces any existing one with the same name and is stored in the data
+    /// directory like other tables, so later queries see the rows until it is dropped.
+    pub fn register_slice<T: Serialize>(&self, name: &str, rows: &[T]) -> Result<Table<'_>, Error> {
+        if rows.is_empty() {
+            return Err(Error::InvalidData(format!(
+                "can't infer the schema of {} from no rows",
+                name
+            )));
+        }
+
+        let file = TempFile::new("jsonl");
+        let mut writer = BufWriter::new(std::fs::File::create(file.path_str()?)?);
+        for row in rows {
+            serde_json::to_writer(&mut writer, row)
+                .map_err(|e| Error::InvalidData(e.to_string()))?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        self.execute(
+            &format!(
+                "CREATE OR REPLACE TABLE {} ENGINE = Log AS SELECT * FROM file({}, 'JSONEachRow')",
+                quote_qualified(name),
+                quote_string(file.path_str()?)
+            ),
+            None,
+        )?;
+
+        Ok(self.table(name))
+    }
+}
@@ -0,0 +1,56 @@
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+
+/// Connection to a ClickHouse server, used through the `remote()` table function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteConfig {
+    address: String,
+    credentials: Option<(String, String)>,
+    secure: bool,
+}
+
+impl RemoteConfig {
+    /// `address` is "host:port" of the native protocol, e.g. "ch-central:9000".
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            credentials: None,
+            secure: false,
+        }
+    }
+
+    pub fn with_credentials(mut self, user: &str, password: &str) -> Self {
+        self.credentials = Some((user.to_string(), password.to_string()));
+        self
+    }
+
+    /// Connects over TLS with `remoteSecure()`.
+    pub fn with_secure(mut self, value: bool) -> Self {
+        self.secure = value;
+        self
+    }
+
+    /// Table function reading and writing `table` ("table" or "db.table") on the server.
+    pub fn table_function(&self, table: &str) -> String {
+        let function = if self.secure {
+            "remoteSecure"
+        } else {
+            "remote"
+        };
+        let mut sql = format!(
+            "{}({}, {}",
+            function,
+            quote_string(&self.address),
+            quote_qualified(table)
+        );
+        if let Some((user, password)) = &self.credentials {
+            sql.push_str(&format!(
+                ", {}, {}",
+                quote_string(user),
+                quote_string(password)
+            ));
+        }
+        sql.push(')');
+        sql
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::format::InputFormat;
+use crate::sql::quote_identifier;
+use crate::table::Table;
+use crate::tsv::parse_u64;
+
+/// How many rows a ring buffer table keeps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Retention {
+    /// Keeps the newest `rows` rows by `order_by`, trimmed after every insert.
+    ///
+    /// Rows tied with the oldest kept value are kept too.
+    MaxRows { order_by: String, rows: u64 },
+    /// Drops rows whose `column` is older than `age` through a table TTL.
+    ///
+    /// Expired rows are removed when parts merge, so reads can still see them for a while.
+    MaxAge { column: String, age: Duration },
+}
+
+/// Table bounded by a `Retention`, see `Table::as_ring_buffer`.
+pub struct RingBuffer<'a> {
+    table: Table<'a>,
+    retention: Retention,
+}
+
+impl<'a> Table<'a> {
+    /// Bounds the table, setting its TTL for `Retention::MaxAge`.
+    ///
+    /// `Retention::MaxRows` must keep at least one row.
+    pub fn as_ring_buffer(self, retention: Retention) -> Result<RingBuffer<'a>, Error> {
+        if let Retention::MaxRows { rows: 0, .. } = &retention {
+            return Err(Error::InvalidData(
+                "a ring buffer must keep at least one row".to_string(),
+            ));
+        }
+        if let Retention::MaxAge { column, age } = &retention {
+            self.alter(&format!(
+                "MODIFY TTL {} + INTERVAL {} SECOND DELETE",
+                quote_identifier(column),
+                age.as_secs().max(1)
+            ))?;
+        }
+
+        Ok(RingBuffer {
+            table: self,
+            retention,
+        })
+    }
+}
+
+impl<'a> RingBuffer<'a> {
+    pub fn table(&self) -> &Table<'a> {
+        &self.table
+    }
+
+    /// Inserts `data` in `format`, then trims the table.
+    pub fn insert(&self, data: &str, format: InputFormat) -> Result<(), Error> {
+        self.table.session().execute(
+            &format!(
+                "INSERT INTO {} FORMAT {}\n{}",
+                self.table.quoted_name(),
+                format.as_str(),
+                data
+            ),
+            None,
+        )?;
+
+        self.trim()
+    }
+
+    /// Deletes the rows past `Retention::MaxRows`, a no-op for `Retention::MaxAge`.
+    pub fn trim(&self) -> Result<(), Error> {
+        let Retention::MaxRows { order_by, rows } = &self.retention else {
+            return Ok(());
+        };
+
+        let table = self.table.quoted_name();
+        let count = self
+            .table
+            .session()
+            .query_rows(&format!("SELECT count() FROM {}", table))?
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or_else(|| Error::InvalidData("empty count() result".to_string()))?;
+        if parse_u64(&count)? <= *rows {
+            return Ok(());
+        }
+
+        let order_by = quote_identifier(order_by);
+        self.table.session().execute(
+            &format!(
+                "DELETE FROM {table} WHERE {order_by} < \
+                 (SELECT min({order_by}) FROM (SELECT {order_by} FROM {table} ORDER BY {order_by} DESC LIMIT {rows}))"
+            ),
+            None,
+        )?;
+
+        Ok(())
+    }
+}
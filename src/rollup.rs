@@ -0,0 +1,233 @@
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::scheduler::Scheduler;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+use crate::sql::quote_qualified;
+use crate::timeseries::Aggregation;
+
+/// Pre-aggregated copy of a table at a fixed time granularity.
+///
+/// The destination is an AggregatingMergeTree holding aggregate states per `bucket` and group,
+/// read back with `query_sql`. It is maintained either by a materialized view, updated by
+/// every insert into the source, or by periodic `refresh`es of the complete buckets.
+#[derive(Debug, Clone)]
+pub struct Rollup {
+    src_table: String,
+    dst_table: String,
+    group_expr: String,
+    aggregations: Vec<(String, Aggregation, String)>,
+    interval: Duration,
+    time_column: String,
+}
+
+impl Rollup {
+    /// `group_expr` is a raw SQL list such as "service, region", or empty. Each aggregation is
+    /// `(output column, aggregation, source column)`.
+    pub fn new(
+        src_table: &str,
+        dst_table: &str,
+        group_expr: &str,
+        aggregations: &[(&str, Aggregation, &str)],
+        interval: Duration,
+    ) -> Self {
+        Self {
+            src_table: src_table.to_string(),
+            dst_table: dst_table.to_string(),
+            group_expr: group_expr.trim().to_string(),
+            aggregations: aggregations
+                .iter()
+                .map(|(name, aggregation, column)| {
+                    (name.to_string(), *aggregation, column.to_string())
+                })
+                .collect(),
+            interval,
+            time_column: "ts".to_string(),
+        }
+    }
+
+    /// Column holding the event time, "ts" by default.
+    pub fn with_time_column(mut self, column: &str) -> Self {
+        self.time_column = column.to_string();
+        self
+    }
+
+    /// Creates the destination table if it doesn't exist.
+    pub fn create_table(&self, session: &Session) -> Result<(), Error> {
+        session.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} ENGINE = AggregatingMergeTree ORDER BY ({}) AS {} WHERE 0 GROUP BY {}",
+                quote_qualified(&self.dst_table),
+                self.key(),
+                self.state_select(),
+                self.key()
+            ),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Creates the destination table and "<dst_table>_mv" feeding it from new inserts into the
+    /// source.
+    ///
+    /// Before the view is attached, the source rows in buckets newer than the destination's
+    /// latest are aggregated with `backfill_sql`, so existing rows are included. Inserts
+    /// running concurrently with the call may be missed. Nothing is done if the view exists.
+    pub fn create_materialized_view(&self, session: &Session) -> Result<(), Error> {
+        let view = quote_qualified(&format!("{}_mv", self.dst_table));
+        let exists = session.query_rows(&format!("EXISTS TABLE {}", view))?;
+        if exists.iter().flatten().next().map(String::as_str) == Some("1") {
+            return Ok(());
+        }
+
+        self.create_table(session)?;
+        session.execute(&self.backfill_sql(), None)?;
+        session.execute(
+            &format!(
+                "CREATE MATERIALIZED VIEW IF NOT EXISTS {} TO {} AS {} GROUP BY {}",
+                view,
+                quote_qualified(&self.dst_table),
+                self.state_select(),
+                self.key()
+            ),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Inserts aggregates for all buckets newer than the destination's latest, including the
+    /// current one.
+    pub fn backfill_sql(&self) -> String {
+        format!(
+            "INSERT INTO {dst} {select} WHERE {bucket} > (SELECT max(bucket) FROM {dst}) \
+             GROUP BY {key}",
+            dst = quote_qualified(&self.dst_table),
+            select = self.state_select(),
+            bucket = self.bucket_expr(),
+            key = self.key()
+        )
+    }
+
+    /// Inserts aggregates for the complete buckets newer than the destination's latest.
+    pub fn refresh_sql(&self) -> String {
+        let bucket = self.bucket_expr();
+        format!(
+            "INSERT INTO {dst} {select} WHERE {bucket} > (SELECT max(bucket) FROM {dst}) \
+             AND {bucket} < toStartOfInterval(now(), {interval}) GROUP BY {key}",
+            dst = quote_qualified(&self.dst_table),
+            select = self.state_select(),
+            interval = self.interval_sql(),
+            key = self.key()
+        )
+    }
+
+    pub fn refresh(&self, session: &Session) -> Result<(), Error> {
+        session.execute(&self.refresh_sql(), None)?;
+        Ok(())
+    }
+
+    /// Adds a job refreshing the rollup once per bucket interval. The destination table
+    /// must exist.
+    pub fn schedule(&self, scheduler: Scheduler) -> Scheduler {
+        scheduler.with_job(
+            &format!("rollup-{}", self.dst_table),
+            &self.refresh_sql(),
+            Duration::from_secs(self.interval_secs()),
+        )
+    }
+
+    /// Final aggregates per bucket and group, ordered by bucket.
+    pub fn query_sql(&self) -> String {
+        let merged = self
+            .aggregations
+            .iter()
+            .map(|(name, aggregation, _)| {
+                let name = quote_identifier(name);
+                format!("{}Merge({}) AS {}", function(*aggregation), name, name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "SELECT {}, {} FROM {} GROUP BY {} ORDER BY {}",
+            self.key(),
+            merged,
+            quote_qualified(&self.dst_table),
+            self.key(),
+            self.key()
+        )
+    }
+
+    /// `SELECT <bucket>, <group>, <states> FROM <src>`, without the GROUP BY.
+    fn state_select(&self) -> String {
+        let time = quote_identifier(&self.time_column);
+        let states = self
+            .aggregations
+            .iter()
+            .map(|(name, aggregation, column)| {
+                let column = quote_identifier(column);
+                let arguments = match aggregation {
+                    Aggregation::First | Aggregation::Last => format!("{}, {}", column, time),
+                    _ => column,
+                };
+                format!(
+                    "{}State({}) AS {}",
+                    function(*aggregation),
+                    arguments,
+                    quote_identifier(name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut columns = format!("{} AS bucket", self.bucket_expr());
+        if !self.group_expr.is_empty() {
+            columns.push_str(&format!(", {}", self.group_expr));
+        }
+        format!(
+            "SELECT {}, {} FROM {}",
+            columns,
+            states,
+            quote_qualified(&self.src_table)
+        )
+    }
+
+    fn key(&self) -> String {
+        if self.group_expr.is_empty() {
+            "bucket".to_string()
+        } else {
+            format!("bucket, {}", self.group_expr)
+        }
+    }
+
+    fn bucket_expr(&self) -> String {
+        format!(
+            "toStartOfInterval({}, {})",
+            quote_identifier(&self.time_column),
+            self.interval_sql()
+        )
+    }
+
+    fn interval_secs(&self) -> u64 {
+        self.interval.as_secs().max(1)
+    }
+
+    fn interval_sql(&self) -> String {
+        format!("INTERVAL {} SECOND", self.interval_secs())
+    }
+}
+
+fn function(aggregation: Aggregation) -> &'static str {
+    match aggregation {
+        Aggregation::Avg => "avg",
+        Aggregation::Sum => "sum",
+        Aggregation::Min => "min",
+        Aggregation::Max => "max",
+        Aggregation::Count => "count",
+        Aggregation::First => "argMin",
+        Aggregation::Last => "argMax",
+    }
+}
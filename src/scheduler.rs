@@ -0,0 +1,176 @@
+//! Runs queries against a session at fixed intervals.
+//!
+//! Every job gets its own thread and runs one at a time: a run taking longer than the interval
+//! delays the next one instead of overlapping it, missed runs are skipped.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::error::Error;
+use crate::session::Session;
+
+pub type ErrorCallback = Arc<dyn Fn(&str, &Error) + Send + Sync>;
+
+pub struct Scheduler {
+    session: Arc<Session>,
+    jobs: Vec<Job>,
+    jitter: Duration,
+    on_error: Option<ErrorCallback>,
+}
+
+struct Job {
+    name: String,
+    script: String,
+    interval: Duration,
+}
+
+/// Running scheduler, stops its jobs when dropped.
+pub struct SchedulerHandle {
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+    workers: Vec<JoinHandle<()>>,
+    errors: Arc<Mutex<HashMap<String, Error>>>,
+}
+
+impl Scheduler {
+    pub fn new(session: Arc<Session>) -> Self {
+        Self {
+            session,
+            jobs: Vec::new(),
+            jitter: Duration::ZERO,
+            on_error: None,
+        }
+    }
+
+    /// Runs `script` (one or more statements) every `interval`, the first time one interval
+    /// after `start`.
+    pub fn with_job(mut self, name: &str, script: &str, interval: Duration) -> Self {
+        self.jobs.push(Job {
+            name: name.to_string(),
+            script: script.to_string(),
+            interval,
+        });
+        self
+    }
+
+    /// Delays every run by a random duration up to `max`, so jobs sharing an interval don't
+    /// all hit the engine at once.
+    pub fn with_jitter(mut self, max: Duration) -> Self {
+        self.jitter = max;
+        self
+    }
+
+    /// Called with the job name when a run fails. Without a callback the latest failure of
+    /// every job is kept for `SchedulerHandle::take_errors`. Failed jobs keep their schedule.
+    pub fn on_error(mut self, callback: impl Fn(&str, &Error) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn start(self) -> Result<SchedulerHandle, Error> {
+        let stopped = Arc::new((Mutex::new(false), Condvar::new()));
+        let mut handle = SchedulerHandle {
+            stopped: stopped.clone(),
+            workers: Vec::new(),
+            errors: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        for job in self.jobs {
+            let session = self.session.clone();
+            let stopped = stopped.clone();
+            let on_error = self.on_error.clone();
+            let errors = handle.errors.clone();
+            let jitter = self.jitter;
+
+            let worker = std::thread::Builder::new()
+                .name(format!("chdb-job-{}", job.name))
+                .spawn(move || {
+                    let mut next = Instant::now() + job.interval;
+                    while wait_until(&stopped, next + random_up_to(jitter)) {
+                        if let Err(e) = session.execute_script(&job.script, None) {
+                            match &on_error {
+                                Some(callback) => callback(&job.name, &e),
+                                None => {
+                                    errors
+                                        .lock()
+                                        .unwrap_or_else(|e| e.into_inner())
+                                        .insert(job.name.clone(), e);
+                                }
+                            }
+                        }
+
+                        next += job.interval;
+                        let now = Instant::now();
+                        while next <= now {
+                            next += job.interval.max(Duration::from_millis(1));
+                        }
+                    }
+                })?;
+            handle.workers.push(worker);
+        }
+
+        Ok(handle)
+    }
+}
+
+impl SchedulerHandle {
+    /// Latest failure of every job that failed since the last call, by job name. Always
+    /// empty with `Scheduler::on_error`.
+    pub fn take_errors(&self) -> HashMap<String, Error> {
+        std::mem::take(&mut *self.errors.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Stops the jobs, waiting for running ones to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let (stopped, condvar) = &*self.stopped;
+        *stopped.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        condvar.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Sleeps until `deadline`, returns false if the scheduler was stopped meanwhile.
+fn wait_until(stopped: &(Mutex<bool>, Condvar), deadline: Instant) -> bool {
+    let (stopped, condvar) = stopped;
+    let mut guard = stopped.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        if *guard {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        guard = condvar
+            .wait_timeout(guard, deadline - now)
+            .unwrap_or_else(|e| e.into_inner())
+            .0;
+    }
+}
+
+fn random_up_to(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let random = RandomState::new().hash_one(Instant::now());
+    Duration::from_nanos(random % max.as_nanos().min(u64::MAX as u128) as u64)
+}
@@ -0,0 +1,174 @@
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::execute;
+use crate::format::InputFormat;
+use crate::format::OutputFormat;
+use crate::session::Session;
+use crate::sql::quote_string;
+use crate::table::Table;
+use crate::table::TableBuilder;
+use crate::tsv;
+use crate::tsv::unexpected_columns;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Column {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_name: String,
+}
+
+/// Column of a query result as reported by `DESCRIBE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub type_name: String,
+    /// `DEFAULT`, `MATERIALIZED`, `ALIAS` or empty.
+    pub default_kind: String,
+    pub default_expression: String,
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+/// Columns of every user table of a session, see `Session::export_schema_snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SchemaSnapshot {
+    pub tables: Vec<TableSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableSchema {
+    pub database: String,
+    pub name: String,
+    pub columns: Vec<Column>,
+}
+
+impl Schema {
+    /// MergeTree table builder with the schema's columns.
+    pub fn to_table_builder(&self, name: &str) -> TableBuilder {
+        self.columns
+            .iter()
+            .fold(TableBuilder::new(name), |builder, column| {
+                builder.with_column(&column.name, &column.type_name)
+            })
+    }
+
+    pub(crate) fn from_describe(rows: Vec<Vec<String>>) -> Result<Self, Error> {
+        let columns = rows
+            .into_iter()
+            .map(|row| {
+                let mut fields = row.into_iter();
+                match (fields.next(), fields.next()) {
+                    (Some(name), Some(type_name)) => Ok(Column { name, type_name }),
+                    _ => Err(Error::InvalidData("malformed DESCRIBE output".to_string())),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { columns })
+    }
+}
+
+impl ColumnInfo {
+    pub fn is_nullable(&self) -> bool {
+        self.type_name.starts_with("Nullable(")
+    }
+}
+
+impl Session {
+    /// Columns `query` would return, without running it.
+    pub fn describe(&self, query: &str) -> Result<Vec<ColumnInfo>, Error> {
+        let rows = self.query_rows(&format!("DESCRIBE ({})", query))?;
+
+        rows.into_iter()
+            .map(|row| {
+                if row.len() < 5 {
+                    return Err(unexpected_columns(5, row.len()));
+                }
+                let mut fields = row.into_iter();
+                let mut next = || fields.next().unwrap_or_default();
+                Ok(ColumnInfo {
+                    name: next(),
+                    type_name: next(),
+                    default_kind: next(),
+                    default_expression: next(),
+                    comment: next(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Session {
+    /// Columns of all tables outside the system databases, ordered by database and table.
+    pub fn schema_snapshot(&self) -> Result<SchemaSnapshot, Error> {
+        let rows = self.query_rows(
+            "SELECT database, table, name, type FROM system.columns \
+             WHERE database NOT IN ('system', 'INFORMATION_SCHEMA', 'information_schema') \
+             ORDER BY database, table, position",
+        )?;
+
+        let mut snapshot = SchemaSnapshot::default();
+        for row in rows {
+            let [database, table, name, type_name] =
+                <[String; 4]>::try_from(row).map_err(|row| unexpected_columns(4, row.len()))?;
+
+            let is_same_table = snapshot
+                .tables
+                .last()
+                .is_some_and(|t| t.database == database && t.name == table);
+            if !is_same_table {
+                snapshot.tables.push(TableSchema {
+                    database,
+                    name: table,
+                    columns: Vec::new(),
+                });
+            }
+            if let Some(table) = snapshot.tables.last_mut() {
+                table.columns.push(Column { name, type_name });
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Writes `schema_snapshot` as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    pub fn export_schema_snapshot(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(&self.schema_snapshot()?)
+            .map_err(|e| Error::InvalidData(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+impl<'a> Table<'a> {
+    pub fn schema(&self) -> Result<Schema, Error> {
+        let rows = self
+            .session()
+            .query_rows(&format!("DESCRIBE TABLE {}", self.quoted_name()))?;
+        Schema::from_describe(rows)
+    }
+}
+
+/// Infers the schema of a file the way `SELECT * FROM file(path, format)` would read it.
+pub fn infer_from_file(path: &str, format: InputFormat) -> Result<Schema, Error> {
+    let result = execute(
+        &format!(
+            "DESCRIBE file({}, {})",
+            quote_string(path),
+            quote_string(format.as_str())
+        ),
+        Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+    )?;
+
+    let rows = tsv::parse(result.data_ref())?;
+
+    Schema::from_describe(rows)
+}
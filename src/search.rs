@@ -0,0 +1,97 @@
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::index::IndexType;
+use crate::query_result::QueryResult;
+use crate::sql::quote_identifier;
+use crate::sql::quote_string;
+use crate::table::Table;
+
+/// Condition matching rows whose `column` contains every whitespace-separated term of `query`.
+///
+/// Terms made of token characters use `hasToken`, which can be served by a token bloom filter
+/// index; other terms fall back to a substring match.
+pub fn search_condition(column: &str, query: &str) -> String {
+    let column = quote_identifier(column);
+    let conditions: Vec<String> = query
+        .split_whitespace()
+        .map(|term| {
+            if is_token(term) {
+                format!("hasToken({}, {})", column, quote_string(term))
+            } else {
+                format!("position({}, {}) > 0", column, quote_string(term))
+            }
+        })
+        .collect();
+
+    if conditions.is_empty() {
+        return "1".to_string();
+    }
+    conditions.join(" AND ")
+}
+
+/// Condition matching rows whose `column` contains at least one of `needles` as a substring.
+pub fn search_any_condition(column: &str, needles: &[&str]) -> String {
+    let needles: Vec<String> = needles.iter().map(|n| quote_string(n)).collect();
+    format!(
+        "multiSearchAny({}, [{}])",
+        quote_identifier(column),
+        needles.join(", ")
+    )
+}
+
+impl<'a> Table<'a> {
+    pub fn search(
+        &self,
+        text_column: &str,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        self.session().execute(
+            &format!(
+                "SELECT * FROM {} WHERE {}",
+                self.quoted_name(),
+                search_condition(text_column, query)
+            ),
+            query_args,
+        )
+    }
+
+    /// Adds a `tokenbf_v1` index, used by `hasToken` and equality lookups on words.
+    pub fn add_token_index(&self, name: &str, column: &str, granularity: u64) -> Result<(), Error> {
+        self.add_index(
+            name,
+            &quote_identifier(column),
+            IndexType::TokenBF {
+                size_bytes: 10240,
+                hash_functions: 3,
+                seed: 0,
+            },
+            granularity,
+        )
+    }
+
+    /// Adds an `ngrambf_v1` index with n-grams of size `n`, used by `LIKE` and `multiSearchAny`.
+    pub fn add_ngram_index(
+        &self,
+        name: &str,
+        column: &str,
+        n: u8,
+        granularity: u64,
+    ) -> Result<(), Error> {
+        self.add_index(
+            name,
+            &quote_identifier(column),
+            IndexType::NgramBF {
+                n,
+                size_bytes: 10240,
+                hash_functions: 3,
+                seed: 0,
+            },
+            granularity,
+        )
+    }
+}
+
+fn is_token(term: &str) -> bool {
+    term.bytes().all(|b| b.is_ascii_alphanumeric() || b >= 0x80)
+}
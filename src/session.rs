@@ -1,26 +1,169 @@
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 use crate::arg::Arg;
 use crate::arg_clickhouse;
 use crate::arg_data_path;
 use crate::arg_query;
+use crate::audit::AuditEntry;
+use crate::audit::AuditLog;
+use crate::audit::AuditSink;
 use crate::call_chdb;
+use crate::config::EngineConfig;
+use crate::default_format_arg;
 use crate::error::Error;
+use crate::format::OutputFormat;
+use crate::intercept::Statement;
+use crate::intercept::StatementInterceptor;
+use crate::log_level::LogLevel;
+use crate::query::Query;
 use crate::query_result::QueryResult;
+use crate::query_result::QueryStats;
+use crate::settings::merge_settings;
+use crate::settings::setting;
+use crate::settings::setting_value;
+use crate::settings::ExecuteOptions;
+use crate::settings::Preset;
+use crate::sql::insert_data_offset;
+use crate::sql::prepend_ctes;
+use crate::sql::quote_identifier;
+use crate::sql::quote_string;
+use crate::sql::split_statements;
+use crate::sql::values_offset;
+use crate::stats::StatsCache;
+use crate::table::Table;
+use crate::temp::TempFile;
+use crate::transform::ResultTransform;
+use crate::tsv;
 
+/// Builds a `Session`.
+///
+/// Engine settings are merged by name in `build`: when several calls set the same setting,
+/// e.g. `with_preset` and `with_max_threads`, the call made last wins.
 pub struct SessionBuilder<'a> {
     data_path: PathBuf,
     default_args: Vec<Arg<'a>>,
-    auto_cleanup: bool,
+    default_format: Option<OutputFormat>,
+    cleanup: CleanupPolicy,
+    slow_query_threshold: Option<Duration>,
+    slow_query_callback: Option<SlowQueryCallback>,
+    query_complete_callback: Option<QueryCompleteCallback>,
+    error_callback: Option<ErrorCallback>,
+    open_mode: OpenMode,
+    max_disk_usage: Option<u64>,
+    memory_soft_limit: Option<u64>,
+    identifier_suggestions: bool,
+    config: EngineConfig,
+    audit: Option<AuditSink>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OpenMode {
+    Any,
+    CreateNew,
+    OpenExisting,
 }
 
 #[derive(Clone)]
 pub struct Session {
     default_args: Vec<CString>,
+    default_format: Option<OutputFormat>,
     data_path: String,
-    auto_cleanup: bool,
+    cleanup: CleanupPolicy,
+    slow_query_threshold: Option<Duration>,
+    slow_query_callback: Option<SlowQueryCallback>,
+    query_complete_callback: Option<QueryCompleteCallback>,
+    error_callback: Option<ErrorCallback>,
+    max_disk_usage: Option<u64>,
+    max_query_size: usize,
+    memory_soft_limit: Option<u64>,
+    identifier_suggestions: bool,
+    audit: Option<Arc<AuditLog>>,
+    pub(crate) transforms: Vec<Arc<dyn ResultTransform>>,
+    pub(crate) interceptors: Vec<Arc<dyn StatementInterceptor>>,
+    pub(crate) stats_cache: Option<Arc<StatsCache>>,
+}
+
+/// What happens to the session data directory when the session goes away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupPolicy {
+    /// Keep the directory.
+    Never,
+    /// Delete the directory when the session is dropped.
+    OnDrop,
+    /// Delete the directory when the session is dropped, unless it contains user tables.
+    OnDropIfEmpty,
+    /// Delete the directory only when `Session::close` is called.
+    OnClose,
+}
+
+/// Query that took longer than the session's slow query threshold.
+#[derive(Debug)]
+pub struct SlowQuery<'a> {
+    pub query: &'a str,
+    pub elapsed: Duration,
+    pub rows_read: u64,
+    pub bytes_read: u64,
+}
+
+pub type SlowQueryCallback = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// Statement executed by a session, see `SessionBuilder::on_query_complete`.
+///
+/// Memory usage is not included: the engine reports only the rows and bytes read and the
+/// elapsed time with a result. With `log_queries` enabled it is in the `memory_usage` column
+/// of `system.query_log`, see `Session::queries_by_comment`.
+#[derive(Debug)]
+pub struct CompletedQuery<'a> {
+    pub query: &'a str,
+    /// Wall time of the call, including time spent outside the engine.
+    pub elapsed: Duration,
+    /// Statistics the engine reported with the result, zero for a failed query.
+    pub stats: QueryStats,
+    /// The error of a failed query.
+    pub error: Option<&'a Error>,
+}
+
+pub type QueryCompleteCallback = Arc<dyn Fn(&CompletedQuery) + Send + Sync>;
+
+pub type ErrorCallback = Arc<dyn Fn(&Error) + Send + Sync>;
+
+/// Engine default for `max_query_size`.
+const DEFAULT_MAX_QUERY_SIZE: usize = 262144;
+
+/// File in the data directory holding `SessionBuilder::with_config`.
+const CONFIG_FILE: &str = "chdb-rust-config.xml";
+
+/// Written into data directories created by a session; nothing else is ever deleted.
+const OWNER_MARKER: &str = ".chdb-rust-session";
+
+#[derive(Default)]
+struct Registry {
+    root: Option<PathBuf>,
+    sessions: HashMap<String, Arc<Session>>,
+}
+
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<Registry> {
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Sets the directory under which `Session::named` creates session data paths.
+///
+/// Defaults to "chdb" in the current directory. Sessions opened before the call keep their path.
+pub fn set_registry_root(path: impl Into<PathBuf>) {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.root = Some(path.into());
 }
 
 impl<'a> SessionBuilder<'a> {
@@ -31,7 +174,18 @@ impl<'a> SessionBuilder<'a> {
         Self {
             data_path,
             default_args: Vec::new(),
-            auto_cleanup: false,
+            default_format: None,
+            cleanup: CleanupPolicy::Never,
+            slow_query_threshold: None,
+            slow_query_callback: None,
+            query_complete_callback: None,
+            error_callback: None,
+            open_mode: OpenMode::Any,
+            max_disk_usage: None,
+            memory_soft_limit: None,
+            identifier_suggestions: false,
+            config: EngineConfig::new(),
+            audit: None,
         }
     }
 
@@ -45,33 +199,257 @@ impl<'a> SessionBuilder<'a> {
         self
     }
 
+    /// Output format of queries that don't pass `Arg::OutputFormat` themselves.
+    pub fn with_default_format(mut self, format: OutputFormat) -> Self {
+        self.default_format = Some(format);
+        self
+    }
+
+    pub fn with_log_level(self, level: LogLevel) -> Self {
+        self.with_arg(Arg::LogLevel(level))
+    }
+
+    /// Directory of executable UDF scripts and their `*.xml` function definitions.
+    pub fn with_udf_path(self, path: &str) -> Self {
+        let path = path.trim_end_matches('/');
+        self.with_arg(Arg::Custom(
+            "user_scripts_path".into(),
+            Some(path.to_string().into()),
+        ))
+        .with_arg(Arg::Custom(
+            "user_defined_executable_functions_config".into(),
+            Some(format!("{}/*.xml", path).into()),
+        ))
+    }
+
+    /// Applies the settings of `preset`, overriding the same settings given by earlier calls.
+    pub fn with_preset(mut self, preset: Preset) -> Self {
+        self.default_args.extend(preset.to_args());
+        self
+    }
+
     /// If set Session will delete data directory before it is dropped.
+    ///
+    /// Shorthand for `CleanupPolicy::OnDrop` / `CleanupPolicy::Never`.
     pub fn with_auto_cleanup(mut self, value: bool) -> Self {
-        self.auto_cleanup = value;
+        self.cleanup = if value {
+            CleanupPolicy::OnDrop
+        } else {
+            CleanupPolicy::Never
+        };
+        self
+    }
+
+    pub fn with_cleanup_policy(mut self, policy: CleanupPolicy) -> Self {
+        self.cleanup = policy;
+        self
+    }
+
+    /// Reports queries whose elapsed time exceeds `threshold` to the slow query callback.
+    pub fn with_slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_slow_query_callback(
+        mut self,
+        callback: impl Fn(&SlowQuery) + Send + Sync + 'static,
+    ) -> Self {
+        self.slow_query_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Calls `callback` after every `Session::execute`, successful or not.
+    ///
+    /// The engine doesn't report memory usage per call; use `Session::queries_by_comment` to
+    /// read it from `system.query_log`.
+    pub fn on_query_complete(
+        mut self,
+        callback: impl Fn(&CompletedQuery) + Send + Sync + 'static,
+    ) -> Self {
+        self.query_complete_callback = Some(Arc::new(callback));
         self
     }
 
+    /// Calls `callback` with errors that don't fail a call: audit log writes, skipped cleanup
+    /// and `RemoteCache` refreshes answered from the stale copy. They are dropped without one.
+    pub fn on_error(mut self, callback: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.error_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// If set `build` fails with `Error::PathNotEmpty` when the data path already holds chDB data.
+    pub fn create_new(mut self, value: bool) -> Self {
+        self.open_mode = if value {
+            OpenMode::CreateNew
+        } else {
+            OpenMode::Any
+        };
+        self
+    }
+
+    /// `build` fails with `Error::NoExistingData` unless the data path already holds chDB data.
+    pub fn open_existing(mut self) -> Self {
+        self.open_mode = OpenMode::OpenExisting;
+        self
+    }
+
+    /// Limits the size of the data directory.
+    ///
+    /// Inserts are rejected with `Error::DiskQuotaExceeded` once the directory reaches `bytes`,
+    /// and the engine's temporary data per query is capped to the same amount.
+    pub fn with_max_disk_usage(mut self, bytes: u64) -> Self {
+        self.max_disk_usage = Some(bytes);
+        self.with_arg(setting("max_temporary_data_on_disk_size_for_query", bytes))
+    }
+
+    /// Session-wide `ExecuteOptions::precise_json_numbers`: 64-bit and wider integers and
+    /// decimals come out of JSON formats as strings instead of lossy numbers.
+    pub fn with_precise_json_numbers(self, value: bool) -> Self {
+        let value = u8::from(value);
+        self.with_arg(setting("output_format_json_quote_64bit_integers", value))
+            .with_arg(setting("output_format_json_quote_decimals", value))
+    }
+
+    /// Caps the threads a single query uses, the engine defaults to one per core.
+    pub fn with_max_threads(self, threads: u64) -> Self {
+        self.with_arg(setting("max_threads", threads))
+    }
+
+    /// Nice value (-20 to 19) of the engine's query threads on Linux, e.g. 10 to keep the host
+    /// application responsive during heavy queries. Raising priority needs `CAP_SYS_NICE`.
+    pub fn with_os_thread_priority(self, nice: i8) -> Self {
+        self.with_arg(setting("os_thread_priority", nice.clamp(-20, 19)))
+    }
+
+    /// Cancels queries whose memory usage goes over `bytes` with `Error::MemorySoftLimit`.
+    ///
+    /// The engine's memory tracker enforces the limit while the query runs, so set it below
+    /// what the host can spare to fail before the OS OOM killer steps in.
+    pub fn with_memory_soft_limit(mut self, bytes: u64) -> Self {
+        self.memory_soft_limit = Some(bytes);
+        self.with_arg(setting("max_memory_usage", bytes))
+    }
+
+    /// Engine configuration, written to `chdb-rust-config.xml` in the data directory by `build`.
+    ///
+    /// Can be called repeatedly, later values win. Don't combine with `Arg::ConfigFilePath`.
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        self.config.merge(config);
+        self
+    }
+
+    /// Spills sorts and aggregations to `dir` once they use more than `threshold` bytes of
+    /// memory, so they complete on small-RAM devices instead of failing.
+    pub fn with_spill_to_disk(self, dir: impl AsRef<Path>, threshold: u64) -> Result<Self, Error> {
+        let config = EngineConfig::new().with_tmp_path(dir)?;
+        Ok(self
+            .with_config(config)
+            .with_arg(setting("max_bytes_before_external_sort", threshold))
+            .with_arg(setting("max_bytes_before_external_group_by", threshold)))
+    }
+
+    /// Reports unknown tables and columns as `Error::UnknownName` with similarly named existing
+    /// ones, at the cost of a schema lookup per such error.
+    pub fn with_identifier_suggestions(mut self, value: bool) -> Self {
+        self.identifier_suggestions = value;
+        self
+    }
+
+    /// Makes query results reproducible where the engine allows it: a fixed UTC session time
+    /// zone and single-threaded execution, so unordered results and floating point aggregates
+    /// come out the same on every run.
+    ///
+    /// `now()` and `rand()` can't be frozen by the engine; pass explicit times and use seeded
+    /// functions such as `generateRandom(structure, seed)` in tests.
+    pub fn deterministic(self, value: bool) -> Self {
+        if !value {
+            return self;
+        }
+        self.with_arg(setting("session_timezone", "UTC"))
+            .with_arg(setting("max_threads", 1))
+    }
+
+    /// Records every executed statement, see `Session::audit_entries`.
+    ///
+    /// Failing to record an entry doesn't fail the query, the error is printed to stderr.
+    pub fn with_audit(mut self, sink: AuditSink) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Prepares the data directory. The engine itself is only started by the first query, so
+    /// building a session that is never queried is cheap.
     pub fn build(self) -> Result<Session, Error> {
         let data_path = self.data_path.to_str().ok_or(Error::PathError)?.to_string();
 
+        let has_data = self.data_path.join("metadata").is_dir();
+        match self.open_mode {
+            OpenMode::CreateNew if has_data => return Err(Error::PathNotEmpty(data_path)),
+            OpenMode::OpenExisting if !has_data => return Err(Error::NoExistingData(data_path)),
+            _ => {}
+        }
+
+        let owned = match fs::read_dir(&self.data_path) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => true,
+        };
+
         fs::create_dir_all(&self.data_path)?;
         if fs::metadata(&self.data_path)?.permissions().readonly() {
             return Err(Error::InsufficientPermissions);
         }
 
-        let mut default_args = Vec::with_capacity(self.default_args.len() + 2);
+        if owned {
+            fs::write(self.data_path.join(OWNER_MARKER), "")?;
+        }
+
+        let settings = merge_settings(self.default_args);
+        let max_query_size = max_query_size_arg(&settings)?.unwrap_or(DEFAULT_MAX_QUERY_SIZE);
+        // A later call such as `with_preset` may have replaced the soft limit.
+        let memory_soft_limit = self.memory_soft_limit.filter(|limit| {
+            setting_value(&settings, "max_memory_usage") == Some(limit.to_string().as_str())
+        });
+
+        let mut default_args = Vec::with_capacity(settings.len() + 2);
         default_args.push(arg_clickhouse()?);
         default_args.push(arg_data_path(&data_path)?);
 
-        for default_arg in self.default_args {
+        if !self.config.is_empty() {
+            let path = self.data_path.join(CONFIG_FILE);
+            fs::write(&path, self.config.to_xml())?;
+            let path = path.to_str().ok_or(Error::PathError)?;
+            default_args.push(Arg::ConfigFilePath(path.into()).to_cstring()?);
+        }
+
+        for default_arg in settings {
             default_args.push(default_arg.to_cstring()?);
         }
 
-        Ok(Session {
+        let session = Session {
             data_path,
             default_args,
-            auto_cleanup: self.auto_cleanup,
-        })
+            default_format: self.default_format,
+            cleanup: self.cleanup,
+            slow_query_threshold: self.slow_query_threshold,
+            slow_query_callback: self.slow_query_callback,
+            query_complete_callback: self.query_complete_callback,
+            error_callback: self.error_callback,
+            max_disk_usage: self.max_disk_usage,
+            max_query_size,
+            memory_soft_limit,
+            identifier_suggestions: self.identifier_suggestions,
+            audit: self.audit.map(|sink| Arc::new(AuditLog::new(sink))),
+            transforms: Vec::new(),
+            interceptors: Vec::new(),
+            stats_cache: None,
+        };
+
+        if let Some(audit) = &session.audit {
+            audit.init(&session)?;
+        }
+
+        Ok(session)
     }
 }
 
@@ -82,11 +460,168 @@ impl<'a> Default for SessionBuilder<'a> {
 }
 
 impl Session {
-    pub fn execute(
+    /// Returns the process-wide session registered under `name`, opening it on first use.
+    ///
+    /// The data path is `<registry root>/<name>`.
+    pub fn named(name: &str) -> Result<Arc<Session>, Error> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(Error::InvalidData(format!(
+                "invalid session name: {}",
+                name
+            )));
+        }
+
+        let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(session) = registry.sessions.get(name) {
+            return Ok(session.clone());
+        }
+
+        let mut data_path = match &registry.root {
+            Some(root) => root.clone(),
+            None => {
+                let mut path = std::env::current_dir()?;
+                path.push("chdb");
+                path
+            }
+        };
+        data_path.push(name);
+
+        let session = Arc::new(SessionBuilder::new().with_data_path(data_path).build()?);
+        registry.sessions.insert(name.to_string(), session.clone());
+
+        Ok(session)
+    }
+
+    pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        if self.interceptors.is_empty() {
+            return self.execute_observed(query, query_args);
+        }
+
+        let mut statement = Statement {
+            sql: query.to_string(),
+            args: query_args
+                .unwrap_or_default()
+                .iter()
+                .map(|arg| arg.clone().into_owned())
+                .collect(),
+        };
+        for interceptor in &self.interceptors {
+            interceptor.intercept(&mut statement);
+        }
+
+        self.execute_observed(&statement.sql, Some(&statement.args))
+    }
+
+    fn execute_observed(
+        &self,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        let Some(callback) = &self.query_complete_callback else {
+            return self.execute_transformed(query, query_args);
+        };
+
+        let start = Instant::now();
+        let result = self.execute_transformed(query, query_args);
+
+        callback(&CompletedQuery {
+            query,
+            elapsed: start.elapsed(),
+            stats: result.as_ref().map(QueryResult::stats).unwrap_or_default(),
+            error: result.as_ref().err(),
+        });
+
+        result
+    }
+
+    fn execute_transformed(
         &self,
         query: &str,
         query_args: Option<&[Arg]>,
-    ) -> Result<Option<QueryResult>, Error> {
+    ) -> Result<QueryResult, Error> {
+        let result = self.execute_audited(query, query_args)?;
+        self.transforms
+            .iter()
+            .try_fold(result, |result, transform| {
+                transform.transform(query, result)
+            })
+    }
+
+    fn execute_audited(
+        &self,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        let Some(audit) = &self.audit else {
+            return self.execute_raw(query, query_args);
+        };
+
+        let mut entry = AuditEntry::new(query, query_args, SystemTime::now());
+        let start = Instant::now();
+        let result = self.execute_raw(query, query_args);
+
+        entry.elapsed = start.elapsed();
+        match &result {
+            Ok(result) => {
+                entry.success = true;
+                entry.rows_read = result.rows_read();
+            }
+            Err(e) => entry.error = e.to_string(),
+        }
+        if let Err(e) = audit.record(self, entry) {
+            self.report_error(&e);
+        }
+
+        result
+    }
+
+    /// Entries recorded by the audit sink, oldest first. Empty without `with_audit`.
+    pub fn audit_entries(&self) -> Result<Vec<AuditEntry>, Error> {
+        match &self.audit {
+            Some(audit) => audit.entries(self),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// `execute` without auditing.
+    pub(crate) fn execute_raw(
+        &self,
+        query: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        self.check_disk_usage(query)?;
+
+        let limit = match query_args {
+            Some(args) => max_query_size_arg(args)?.unwrap_or(self.max_query_size),
+            None => self.max_query_size,
+        };
+        if query.len() > limit {
+            return self.execute_oversized(query, query_args, limit);
+        }
+
+        self.dispatch(query, query_args)
+    }
+
+    /// Checks a query over `max_query_size`. The data of `INSERT ... VALUES` and
+    /// `INSERT ... FORMAT` is read by the format parser and doesn't count, so such inserts are
+    /// sent whole, keeping them atomic, unless their head alone is too large.
+    fn execute_oversized(
+        &self,
+        query: &str,
+        query_args: Option<&[Arg]>,
+        limit: usize,
+    ) -> Result<QueryResult, Error> {
+        let size = insert_data_offset(query)
+            .or_else(|| values_offset(query))
+            .unwrap_or(query.len());
+        if size > limit {
+            return Err(Error::QueryTooLarge { size, limit });
+        }
+
+        self.dispatch(query, query_args)
+    }
+
+    fn dispatch(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
         let mut argv = Vec::with_capacity(
             self.default_args.len() + query_args.as_ref().map_or(0, |v| v.len()) + 1,
         );
@@ -101,15 +636,286 @@ impl Session {
             }
         }
 
+        if let Some(arg) = default_format_arg(query_args, self.default_format) {
+            argv.push(arg.to_cstring()?.into_raw());
+        }
+
+        #[cfg(feature = "otel")]
+        if let Some(arg) = crate::otel::traceparent_arg() {
+            argv.push(arg.to_cstring()?.into_raw());
+        }
+
         argv.push(arg_query(query)?.into_raw());
-        call_chdb(argv)
+        let result = call_chdb(argv).map_err(|e| {
+            let e = self.memory_limit_error(e);
+            if self.identifier_suggestions {
+                self.add_suggestions(e)
+            } else {
+                e
+            }
+        })?;
+        self.report_slow_query(query, &result);
+
+        Ok(result)
+    }
+
+    /// Closes the session, applying the cleanup policy and reporting any failure.
+    pub fn close(mut self) -> Result<(), Error> {
+        let cleanup = std::mem::replace(&mut self.cleanup, CleanupPolicy::Never);
+
+        match cleanup {
+            CleanupPolicy::Never => Ok(()),
+            CleanupPolicy::OnDrop | CleanupPolicy::OnClose => self.remove_data_dir(),
+            CleanupPolicy::OnDropIfEmpty => {
+                if self.has_user_tables()? {
+                    return Ok(());
+                }
+                self.remove_data_dir()
+            }
+        }
+    }
+
+    pub fn execute_with(
+        &self,
+        query: &str,
+        options: &ExecuteOptions,
+    ) -> Result<QueryResult, Error> {
+        let null_as = options.null_representation();
+        if options.external_tables().is_empty() {
+            return Ok(self
+                .execute(query, Some(options.args()))?
+                .with_null_as(null_as));
+        }
+
+        let mut files = Vec::new();
+        let mut ctes = Vec::new();
+        for table in options.external_tables() {
+            let file = TempFile::new("data");
+            fs::write(file.path_str()?, &table.data)?;
+            ctes.push(format!(
+                "{} AS (SELECT * FROM file({}, {}, {}))",
+                quote_identifier(&table.name),
+                quote_string(file.path_str()?),
+                quote_string(table.format.as_str()),
+                quote_string(&table.structure)
+            ));
+            files.push(file);
+        }
+
+        let query = prepend_ctes(query, &ctes.join(", ")).ok_or_else(|| {
+            Error::InvalidData(
+                "external tables need a SELECT or INSERT ... SELECT statement".to_string(),
+            )
+        })?;
+        Ok(self
+            .execute(&query, Some(options.args()))?
+            .with_null_as(null_as))
+    }
+
+    /// Renders `query` and runs it, see `Query::render_with_options`.
+    pub fn execute_query(
+        &self,
+        query: &Query,
+        options: &ExecuteOptions,
+    ) -> Result<QueryResult, Error> {
+        let (sql, options) = query.render_with_options(options)?;
+        self.execute_with(&sql, &options)
+    }
+
+    /// Runs every statement of `script` in order, see `sql::split_statements`.
+    ///
+    /// Stops at the first failing statement with `Error::ScriptFailed`.
+    pub fn execute_script(
+        &self,
+        script: &str,
+        query_args: Option<&[Arg]>,
+    ) -> Result<Vec<QueryResult>, Error> {
+        split_statements(script)
+            .into_iter()
+            .enumerate()
+            .map(|(statement_index, statement)| {
+                self.execute(statement, query_args)
+                    .map_err(|e| Error::ScriptFailed {
+                        statement_index,
+                        statement_sql: statement.to_string(),
+                        source: Box::new(e),
+                    })
+            })
+            .collect()
+    }
+
+    pub fn table(&self, name: &str) -> Table<'_> {
+        Table::new(self, name)
+    }
+
+    pub(crate) fn query_rows(&self, query: &str) -> Result<Vec<Vec<String>>, Error> {
+        let result = self.execute(
+            query,
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )?;
+
+        tsv::parse(result.data_ref())
+    }
+
+    fn remove_data_dir(&self) -> Result<(), Error> {
+        if !Path::new(&self.data_path).join(OWNER_MARKER).exists() {
+            return Err(Error::RefusingToDeleteForeignDir(self.data_path.clone()));
+        }
+
+        Ok(fs::remove_dir_all(&self.data_path)?)
+    }
+
+    fn check_disk_usage(&self, query: &str) -> Result<(), Error> {
+        let Some(limit) = self.max_disk_usage else {
+            return Ok(());
+        };
+
+        let is_insert = query
+            .trim_start()
+            .get(..6)
+            .is_some_and(|s| s.eq_ignore_ascii_case("INSERT"));
+        if !is_insert {
+            return Ok(());
+        }
+
+        let used = dir_size(Path::new(&self.data_path))?;
+        if used >= limit {
+            return Err(Error::DiskQuotaExceeded { used, limit });
+        }
+
+        Ok(())
+    }
+
+    fn memory_limit_error(&self, error: Error) -> Error {
+        match (self.memory_soft_limit, error) {
+            (Some(limit), Error::QueryError(message))
+                if message.contains("MEMORY_LIMIT_EXCEEDED") =>
+            {
+                Error::MemorySoftLimit { limit, message }
+            }
+            (_, error) => error,
+        }
+    }
+
+    fn has_user_tables(&self) -> Result<bool, Error> {
+        let rows = self.query_rows(
+            "SELECT count() FROM system.tables \
+             WHERE database NOT IN ('system', 'INFORMATION_SCHEMA', 'information_schema')",
+        )?;
+
+        Ok(rows.first().and_then(|row| row.first()).map(String::as_str) != Some("0"))
+    }
+
+    fn report_slow_query(&self, query: &str, result: &QueryResult) {
+        let (Some(threshold), Some(callback)) =
+            (self.slow_query_threshold, &self.slow_query_callback)
+        else {
+            return;
+        };
+
+        let elapsed = result.elapsed();
+        if elapsed < threshold {
+            return;
+        }
+
+        let slow_query = SlowQuery {
+            query,
+            elapsed,
+            rows_read: result.rows_read(),
+            bytes_read: result.bytes_read(),
+        };
+
+        callback(&slow_query);
+    }
+
+    /// Passes an error that doesn't fail the call to `SessionBuilder::on_error`.
+    pub(crate) fn report_error(&self, error: &Error) {
+        if let Some(callback) = &self.error_callback {
+            callback(error);
+        }
     }
 }
 
 impl Drop for Session {
     fn drop(&mut self) {
-        if self.auto_cleanup {
-            fs::remove_dir_all(&self.data_path).ok();
+        let result = match self.cleanup {
+            CleanupPolicy::OnDrop => self.remove_data_dir(),
+            CleanupPolicy::OnDropIfEmpty => match self.has_user_tables() {
+                Ok(false) => self.remove_data_dir(),
+                _ => Ok(()),
+            },
+            CleanupPolicy::Never | CleanupPolicy::OnClose => Ok(()),
+        };
+
+        if let Err(e @ Error::RefusingToDeleteForeignDir(_)) = result {
+            self.report_error(&e);
+        }
+    }
+}
+
+/// Value of a `max_query_size` setting among `args`.
+fn max_query_size_arg(args: &[Arg]) -> Result<Option<usize>, Error> {
+    for arg in args {
+        if let Arg::Custom(name, Some(value)) = arg {
+            if name == "max_query_size" {
+                return value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| Error::InvalidData(format!("invalid max_query_size: {}", value)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Deletes session directories directly under `root` that were not modified for `max_age`.
+///
+/// Only directories created by a session are considered. Returns the removed paths.
+pub fn cleanup_stale(root: impl AsRef<Path>, max_age: Duration) -> Result<Vec<PathBuf>, Error> {
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        if !path.is_dir() || !path.join(OWNER_MARKER).exists() {
+            continue;
+        }
+
+        let modified = last_modified(&path)?;
+        if now.duration_since(modified).unwrap_or_default() >= max_age {
+            fs::remove_dir_all(&path)?;
+            removed.push(path);
+        }
+    }
+
+    Ok(removed)
+}
+
+fn dir_size(path: &Path) -> Result<u64, Error> {
+    let mut size = 0;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if metadata.is_file() {
+            size += metadata.len();
         }
     }
+
+    Ok(size)
+}
+
+fn last_modified(path: &Path) -> Result<SystemTime, Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut latest = metadata.modified()?;
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            latest = latest.max(last_modified(&entry?.path())?);
+        }
+    }
+
+    Ok(latest)
 }
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use crate::arg::Arg;
+use crate::format::InputFormat;
+use crate::schema::Schema;
+use crate::sql::quote_identifier;
+
+#[derive(Debug, Clone, Copy)]
+pub enum DateTimeInputFormat {
+    Basic,
+    BestEffort,
+    BestEffortUs,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DateTimeOutputFormat {
+    Simple,
+    Iso,
+    UnixTimestamp,
+}
+
+/// CSV dialect, applies to both reading (inserts, `file()`) and writing CSV.
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    delimiter: Option<char>,
+    allow_single_quotes: Option<bool>,
+    allow_double_quotes: Option<bool>,
+    null_representation: Option<String>,
+    crlf_end_of_line: Option<bool>,
+    common: CommonTextOptions,
+}
+
+/// TSV dialect, applies to both reading and writing TabSeparated formats.
+#[derive(Debug, Clone, Default)]
+pub struct TsvOptions {
+    null_representation: Option<String>,
+    crlf_end_of_line: Option<bool>,
+    common: CommonTextOptions,
+}
+
+/// Rendering of the `Pretty*` output formats.
+#[derive(Debug, Clone, Default)]
+pub struct PrettyOptions {
+    max_rows: Option<u64>,
+    max_value_width: Option<u64>,
+    color: Option<bool>,
+    row_numbers: Option<bool>,
+}
+
+/// Tuned combinations of engine settings, see `SessionBuilder::with_preset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Small devices: two threads, a 1 GiB memory cap, spilling sorts and aggregations to disk
+    /// from 256 MiB.
+    LowMemory,
+    /// Batch jobs: all cores, no memory cap and large insert blocks.
+    Throughput,
+    /// Interactive use next to other work: four threads, a 4 GiB memory cap and a 60 second
+    /// execution time limit.
+    Interactive,
+}
+
+/// Per-query options for `Session::execute_with`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions<'a> {
+    args: Vec<Arg<'a>>,
+    null_as: Option<String>,
+    external_tables: Vec<ExternalTable>,
+}
+
+/// Data visible to a single query as a named table, see `ExecuteOptions::with_external_table`.
+#[derive(Debug, Clone)]
+pub(crate) struct ExternalTable {
+    pub(crate) name: String,
+    pub(crate) structure: String,
+    pub(crate) format: InputFormat,
+    pub(crate) data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CommonTextOptions {
+    skip_first_lines: Option<u64>,
+    use_header: Option<bool>,
+    date_time_input_format: Option<DateTimeInputFormat>,
+    date_time_output_format: Option<DateTimeOutputFormat>,
+}
+
+impl DateTimeInputFormat {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Basic => "basic",
+            Self::BestEffort => "best_effort",
+            Self::BestEffortUs => "best_effort_us",
+        }
+    }
+}
+
+impl DateTimeOutputFormat {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::Iso => "iso",
+            Self::UnixTimestamp => "unix_timestamp",
+        }
+    }
+}
+
+impl Preset {
+    pub fn to_args(self) -> Vec<Arg<'static>> {
+        const MIB: u64 = 1024 * 1024;
+
+        match self {
+            Self::LowMemory => vec![
+                setting("max_threads", 2),
+                setting("max_memory_usage", 1024 * MIB),
+                setting("max_bytes_before_external_sort", 256 * MIB),
+                setting("max_bytes_before_external_group_by", 256 * MIB),
+                setting("max_block_size", 8192),
+            ],
+            Self::Throughput => vec![
+                setting("max_threads", 0),
+                setting("max_memory_usage", 0),
+                setting("max_insert_threads", 0),
+                setting("min_insert_block_size_rows", 4 * 1048576),
+                setting("min_insert_block_size_bytes", 1024 * MIB),
+            ],
+            Self::Interactive => vec![
+                setting("max_threads", 4),
+                setting("max_memory_usage", 4096 * MIB),
+                setting("max_bytes_before_external_sort", 2048 * MIB),
+                setting("max_bytes_before_external_group_by", 2048 * MIB),
+                setting("max_execution_time", 60),
+            ],
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    pub fn with_single_quotes(mut self, value: bool) -> Self {
+        self.allow_single_quotes = Some(value);
+        self
+    }
+
+    pub fn with_double_quotes(mut self, value: bool) -> Self {
+        self.allow_double_quotes = Some(value);
+        self
+    }
+
+    pub fn with_null_representation(mut self, value: &str) -> Self {
+        self.null_representation = Some(value.to_string());
+        self
+    }
+
+    pub fn with_crlf_end_of_line(mut self, value: bool) -> Self {
+        self.crlf_end_of_line = Some(value);
+        self
+    }
+
+    /// Number of lines skipped at the beginning of input.
+    pub fn with_skip_first_lines(mut self, lines: u64) -> Self {
+        self.common.skip_first_lines = Some(lines);
+        self
+    }
+
+    /// Whether `*WithNames` formats map input columns by header names.
+    pub fn with_header(mut self, value: bool) -> Self {
+        self.common.use_header = Some(value);
+        self
+    }
+
+    pub fn with_date_time_input_format(mut self, format: DateTimeInputFormat) -> Self {
+        self.common.date_time_input_format = Some(format);
+        self
+    }
+
+    pub fn with_date_time_output_format(mut self, format: DateTimeOutputFormat) -> Self {
+        self.common.date_time_output_format = Some(format);
+        self
+    }
+
+    pub(crate) fn delimiter(&self) -> Option<char> {
+        self.delimiter
+    }
+
+    pub fn to_args(&self) -> Vec<Arg<'static>> {
+        let mut args = Vec::new();
+        push(&mut args, "format_csv_delimiter", self.delimiter);
+        push_bool(
+            &mut args,
+            "format_csv_allow_single_quotes",
+            self.allow_single_quotes,
+        );
+        push_bool(
+            &mut args,
+            "format_csv_allow_double_quotes",
+            self.allow_double_quotes,
+        );
+        push(
+            &mut args,
+            "format_csv_null_representation",
+            self.null_representation.as_ref(),
+        );
+        push_bool(
+            &mut args,
+            "output_format_csv_crlf_end_of_line",
+            self.crlf_end_of_line,
+        );
+        self.common
+            .push_args(&mut args, "input_format_csv_skip_first_lines");
+        args
+    }
+}
+
+impl TsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_null_representation(mut self, value: &str) -> Self {
+        self.null_representation = Some(value.to_string());
+        self
+    }
+
+    pub fn with_crlf_end_of_line(mut self, value: bool) -> Self {
+        self.crlf_end_of_line = Some(value);
+        self
+    }
+
+    /// Number of lines skipped at the beginning of input.
+    pub fn with_skip_first_lines(mut self, lines: u64) -> Self {
+        self.common.skip_first_lines = Some(lines);
+        self
+    }
+
+    /// Whether `*WithNames` formats map input columns by header names.
+    pub fn with_header(mut self, value: bool) -> Self {
+        self.common.use_header = Some(value);
+        self
+    }
+
+    pub fn with_date_time_input_format(mut self, format: DateTimeInputFormat) -> Self {
+        self.common.date_time_input_format = Some(format);
+        self
+    }
+
+    pub fn with_date_time_output_format(mut self, format: DateTimeOutputFormat) -> Self {
+        self.common.date_time_output_format = Some(format);
+        self
+    }
+
+    pub fn to_args(&self) -> Vec<Arg<'static>> {
+        let mut args = Vec::new();
+        push(
+            &mut args,
+            "format_tsv_null_representation",
+            self.null_representation.as_ref(),
+        );
+        push_bool(
+            &mut args,
+            "output_format_tsv_crlf_end_of_line",
+            self.crlf_end_of_line,
+        );
+        self.common
+            .push_args(&mut args, "input_format_tsv_skip_first_lines");
+        args
+    }
+}
+
+impl PrettyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rows rendered before the output is cut off, 10000 by default.
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Characters shown per value, longer values are cut with an ellipsis.
+    pub fn with_max_value_width(mut self, width: u64) -> Self {
+        self.max_value_width = Some(width);
+        self
+    }
+
+    /// ANSI colors, e.g. off when writing to logs.
+    pub fn with_color(mut self, value: bool) -> Self {
+        self.color = Some(value);
+        self
+    }
+
+    pub fn with_row_numbers(mut self, value: bool) -> Self {
+        self.row_numbers = Some(value);
+        self
+    }
+
+    pub fn to_args(&self) -> Vec<Arg<'static>> {
+        let mut args = Vec::new();
+        push(&mut args, "output_format_pretty_max_rows", self.max_rows);
+        push(
+            &mut args,
+            "output_format_pretty_max_value_width",
+            self.max_value_width,
+        );
+        push_bool(&mut args, "output_format_pretty_color", self.color);
+        push_bool(
+            &mut args,
+            "output_format_pretty_row_numbers",
+            self.row_numbers,
+        );
+        args
+    }
+}
+
+impl<'a> ExecuteOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_arg(mut self, arg: Arg<'a>) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    pub fn with_args(mut self, args: impl IntoIterator<Item = Arg<'a>>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    /// Text representation of NULL in TSV and CSV output, and of NULL in input.
+    ///
+    /// `None` keeps the engine default ("\\N"). JSON formats always use `null`.
+    pub fn null_as(mut self, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => {
+                self.null_as = Some(value.to_string());
+                self.with_arg(setting("format_tsv_null_representation", value))
+                    .with_arg(setting("format_csv_null_representation", value))
+            }
+            None => self,
+        }
+    }
+
+    /// Quotes 64-bit (and wider) integers and decimals in JSON output so they can be parsed
+    /// without going through `f64`, see `chdb_rust::quoted` for deserializing them.
+    pub fn precise_json_numbers(self, value: bool) -> Self {
+        let value = u8::from(value);
+        self.with_arg(setting("output_format_json_quote_64bit_integers", value))
+            .with_arg(setting("output_format_json_quote_decimals", value))
+    }
+
+    /// Tags the query in `system.query_log`, see `Session::queries_by_comment`.
+    pub fn comment(self, comment: &str) -> Self {
+        self.with_arg(setting("log_comment", comment))
+    }
+
+    /// Makes `data` readable as table `name` by this query only, e.g. for `id IN name`
+    /// filters with large id lists. The session schema is not touched. A table of the same
+    /// name given earlier is replaced.
+    pub fn with_external_table(
+        mut self,
+        name: &str,
+        schema: &Schema,
+        data: impl Into<Vec<u8>>,
+        format: InputFormat,
+    ) -> Self {
+        let structure: Vec<String> = schema
+            .columns
+            .iter()
+            .map(|column| format!("{} {}", quote_identifier(&column.name), column.type_name))
+            .collect();
+
+        self.external_tables.retain(|table| table.name != name);
+        self.external_tables.push(ExternalTable {
+            name: name.to_string(),
+            structure: structure.join(", "),
+            format,
+            data: data.into(),
+        });
+        self
+    }
+
+    pub fn args(&self) -> &[Arg<'a>] {
+        &self.args
+    }
+
+    pub(crate) fn null_representation(&self) -> Option<&str> {
+        self.null_as.as_deref()
+    }
+
+    pub(crate) fn external_tables(&self) -> &[ExternalTable] {
+        &self.external_tables
+    }
+}
+
+impl CommonTextOptions {
+    fn push_args(&self, args: &mut Vec<Arg<'static>>, skip_first_lines_setting: &'static str) {
+        push(args, skip_first_lines_setting, self.skip_first_lines);
+        push_bool(args, "input_format_with_names_use_header", self.use_header);
+        push(
+            args,
+            "date_time_input_format",
+            self.date_time_input_format.map(|f| f.as_str()),
+        );
+        push(
+            args,
+            "date_time_output_format",
+            self.date_time_output_format.map(|f| f.as_str()),
+        );
+    }
+}
+
+pub(crate) fn setting(name: &'static str, value: impl ToString) -> Arg<'static> {
+    Arg::Custom(name.into(), Some(value.to_string().into()))
+}
+
+/// Keeps one `Arg::Custom` setting per name: the last value given, at the position of the
+/// first. Other arguments are passed through.
+pub(crate) fn merge_settings(args: Vec<Arg<'_>>) -> Vec<Arg<'_>> {
+    let mut merged: Vec<Arg> = Vec::with_capacity(args.len());
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for arg in args {
+        if let Arg::Custom(name, Some(_)) = &arg {
+            if let Some(&i) = positions.get(name.as_ref()) {
+                merged[i] = arg;
+                continue;
+            }
+            positions.insert(name.to_string(), merged.len());
+        }
+        merged.push(arg);
+    }
+
+    merged
+}
+
+/// Value of setting `name` among `args`, the last one if given repeatedly.
+pub(crate) fn setting_value<'b>(args: &'b [Arg<'_>], name: &str) -> Option<&'b str> {
+    args.iter().rev().find_map(|arg| match arg {
+        Arg::Custom(k, Some(v)) if k == name => Some(v.as_ref()),
+        _ => None,
+    })
+}
+
+fn push(args: &mut Vec<Arg<'static>>, name: &'static str, value: Option<impl ToString>) {
+    if let Some(value) = value {
+        args.push(setting(name, value));
+    }
+}
+
+fn push_bool(args: &mut Vec<Arg<'static>>, name: &'static str, value: Option<bool>) {
+    push(args, name, value.map(u8::from));
+}
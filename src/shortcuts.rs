@@ -0,0 +1,59 @@
+//! One-call helpers over `execute` for quick scripts.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::execute;
+use crate::format::OutputFormat;
+use crate::tsv;
+
+/// First column of the first row of `query`, parsed with `FromStr`.
+pub fn execute_scalar<T>(query: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let result = execute(
+        query,
+        Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+    )?;
+    let value = tsv::parse(result.data_ref())?
+        .into_iter()
+        .next()
+        .and_then(|row| row.into_iter().next())
+        .ok_or_else(|| Error::InvalidData("query returned no rows".to_string()))?;
+
+    value
+        .parse()
+        .map_err(|e| Error::InvalidData(format!("can't parse scalar {:?}: {}", value, e)))
+}
+
+/// Rows of `query` as JSON objects.
+#[cfg(feature = "serde")]
+pub fn execute_json(query: &str) -> Result<serde_json::Value, Error> {
+    Ok(serde_json::Value::Array(execute_rows(query)?))
+}
+
+/// Rows of `query` deserialized from `JSONEachRow`.
+///
+/// 64-bit integers are written as plain numbers; wider integers and decimals need
+/// `chdb_rust::quoted` on the receiving fields.
+#[cfg(feature = "serde")]
+pub fn execute_rows<T: serde::de::DeserializeOwned>(query: &str) -> Result<Vec<T>, Error> {
+    let result = execute(
+        query,
+        Some(&[
+            Arg::OutputFormat(OutputFormat::JSONEachRow),
+            crate::settings::setting("output_format_json_quote_64bit_integers", 0),
+        ]),
+    )?;
+
+    result
+        .data_ref()
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).map_err(|e| Error::InvalidData(e.to_string())))
+        .collect()
+}
@@ -0,0 +1,221 @@
+//! Golden file testing for query outputs.
+//!
+//! Snapshots live in a directory as `<name>.<ext>` files and are (re)written when missing or
+//! when `CHDB_UPDATE_SNAPSHOTS=1` is set. Volatile statistics (`elapsed`) of JSON outputs are
+//! normalized before comparing.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::format::OutputFormat;
+use crate::query_result::QueryResult;
+
+/// JSON keys whose values change between runs.
+const VOLATILE_KEYS: &[&str] = &["elapsed", "query_id"];
+
+/// Largest table the line diff builds before it stops aligning lines, about 16 MiB.
+const MAX_DIFF_CELLS: usize = 1 << 22;
+
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Snapshots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            update: std::env::var("CHDB_UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1"),
+        }
+    }
+
+    /// Compares the output of a query run with `format` against snapshot `name`.
+    ///
+    /// Panics with a line diff on mismatch.
+    pub fn assert_result(&self, name: &str, result: &QueryResult, format: OutputFormat) {
+        self.assert_text(name, extension(format), &result.data_utf8_lossy());
+    }
+
+    pub fn assert_text(&self, name: &str, extension: &str, actual: &str) {
+        let path = self.dir.join(format!("{}.{}", name, extension));
+        let actual = normalize(actual);
+
+        let expected = match std::fs::read_to_string(&path) {
+            Ok(expected) if !self.update => expected,
+            _ => {
+                std::fs::create_dir_all(&self.dir).unwrap();
+                std::fs::write(&path, &actual).unwrap();
+                return;
+            }
+        };
+
+        if expected != actual {
+            panic!(
+                "snapshot {} doesn't match (set CHDB_UPDATE_SNAPSHOTS=1 to update):\n{}",
+                path.display(),
+                diff(&expected, &actual)
+            );
+        }
+    }
+}
+
+/// Replaces the values of volatile keys with `"<key>"` in a JSON output document, one with a
+/// top level `statistics` object, both at the top level and inside `statistics`.
+///
+/// Result rows are left alone, even with columns of the same names, and so is every output
+/// without statistics such as `JSONEachRow`.
+pub fn normalize(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut volatile = Vec::new();
+    let mut has_statistics = false;
+    let mut in_statistics = false;
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let len = json_value_len(&text[i..]);
+                let token = &text[i..i + len];
+                let key = token.get(1..token.len() - 1).unwrap_or_default();
+                i += len;
+                let Some(value) = text[i..].trim_start().strip_prefix(':') else {
+                    continue;
+                };
+                let value_start = text.len() - value.len();
+
+                if depth == 1 && key == "statistics" && value.trim_start().starts_with('{') {
+                    has_statistics = true;
+                    in_statistics = true;
+                } else if let Some(key) = VOLATILE_KEYS.iter().find(|k| **k == key) {
+                    if depth == 1 || (in_statistics && depth == 2) {
+                        let skipped = value.len() - value.trim_start().len();
+                        let value_end = value_start + skipped + json_value_len(&value[skipped..]);
+                        volatile.push((value_start, value_end, key));
+                        i = value_end;
+                    }
+                }
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.saturating_sub(1);
+                if depth <= 1 {
+                    in_statistics = false;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if !has_statistics {
+        return text.to_string();
+    }
+
+    let mut normalized = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end, key) in volatile {
+        normalized.push_str(&text[copied..start]);
+        let _ = write!(normalized, " \"<{}>\"", key);
+        copied = end;
+    }
+    normalized.push_str(&text[copied..]);
+    normalized
+}
+
+/// Length of the scalar JSON value at the start of `text`.
+fn json_value_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    if bytes.first() == Some(&b'"') {
+        let mut i = 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return i + 1,
+                _ => i += 1,
+            }
+        }
+        return bytes.len();
+    }
+
+    text.find([',', '}', ']', '\n']).unwrap_or(text.len())
+}
+
+fn extension(format: OutputFormat) -> &'static str {
+    let name = format.as_str();
+    if name.starts_with("JSON") {
+        "json"
+    } else if name.starts_with("CSV") {
+        "csv"
+    } else if name.starts_with("TabSeparated") || name.starts_with("TSV") {
+        "tsv"
+    } else {
+        "txt"
+    }
+}
+
+/// Line diff based on the longest common subsequence.
+///
+/// Past `MAX_DIFF_CELLS` the lines between the common prefix and suffix are shown as
+/// removed and added without aligning them.
+fn diff(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut out = String::new();
+    for line in &a[..prefix] {
+        let _ = writeln!(out, "  {}", line);
+    }
+    let a_middle = &a[prefix..a.len() - suffix];
+    let b_middle = &b[prefix..b.len() - suffix];
+    if (a_middle.len() + 1).saturating_mul(b_middle.len() + 1) > MAX_DIFF_CELLS {
+        for line in a_middle {
+            let _ = writeln!(out, "- {}", line);
+        }
+        for line in b_middle {
+            let _ = writeln!(out, "+ {}", line);
+        }
+    } else {
+        lcs_diff(&mut out, a_middle, b_middle);
+    }
+    for line in &a[a.len() - suffix..] {
+        let _ = writeln!(out, "  {}", line);
+    }
+    out
+}
+
+fn lcs_diff(out: &mut String, a: &[&str], b: &[&str]) {
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            let _ = writeln!(out, "  {}", a[i]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            let _ = writeln!(out, "+ {}", b[j]);
+            j += 1;
+        } else {
+            let _ = writeln!(out, "- {}", a[i]);
+            i += 1;
+        }
+    }
+}
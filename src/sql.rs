@@ -0,0 +1,485 @@
+use std::collections::HashSet;
+
+/// Quotes a value as a ClickHouse string literal.
+pub fn quote_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\0' => quoted.push_str("\\0"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Quotes a single identifier with backticks.
+pub fn quote_identifier(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('`');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '`' => quoted.push_str("\\`"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('`');
+    quoted
+}
+
+/// Quotes a possibly database-qualified name such as "db.table".
+pub fn quote_qualified(value: &str) -> String {
+    value
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Splits a script into statements on top-level semicolons.
+///
+/// Semicolons inside string literals, quoted identifiers and comments are ignored. For
+/// `INSERT ... FORMAT <format>` with inline data (any format but `Values`), the data runs to
+/// the end of the line, like it does in clickhouse-client. Empty and comment-only statements
+/// are dropped.
+pub fn split_statements(script: &str) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    let mut words = 0;
+    let mut is_insert = false;
+    let mut has_code = false;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' => {
+                i = skip_quoted(bytes, i);
+                has_code = true;
+                words += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i),
+            b'#' => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+            b';' => {
+                if has_code {
+                    statements.push(script[start..i].trim());
+                }
+                i += 1;
+                start = i;
+                words = 0;
+                is_insert = false;
+                has_code = false;
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let word_start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                let word = &script[word_start..i];
+                has_code = true;
+                if words == 0 {
+                    is_insert = word.eq_ignore_ascii_case("INSERT");
+                }
+                words += 1;
+
+                if is_insert && word.eq_ignore_ascii_case("FORMAT") {
+                    if let Some(end) = inline_data_end(script, i) {
+                        statements.push(script[start..end].trim());
+                        i = end;
+                        start = end;
+                        words = 0;
+                        is_insert = false;
+                        has_code = false;
+                    }
+                }
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                has_code = true;
+                i += 1;
+            }
+        }
+    }
+
+    if has_code {
+        statements.push(script[start..].trim());
+    }
+
+    statements
+}
+
+/// Lexical token of a single statement, used for light-weight statement inspection.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token<'a> {
+    /// Keyword or unquoted identifier.
+    Word(&'a str),
+    /// Backtick or double-quoted identifier, without the quotes.
+    Quoted(&'a str),
+    Literal,
+    Punct(u8),
+}
+
+impl<'a> Token<'a> {
+    pub(crate) fn is_word(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Identifier text of a `Word` or `Quoted` token.
+    pub(crate) fn name(&self) -> Option<&'a str> {
+        match *self {
+            Token::Word(name) | Token::Quoted(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a statement into tokens, skipping whitespace and comments.
+pub(crate) fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => {
+                i = skip_quoted(bytes, i);
+                tokens.push(Token::Literal);
+            }
+            b'"' | b'`' => {
+                let end = skip_quoted(bytes, i);
+                let inner_end = end.saturating_sub(1).max(i + 1);
+                tokens.push(Token::Quoted(&sql[i + 1..inner_end]));
+                i = end;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(bytes, i),
+            b'#' => i = skip_line(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push(Token::Word(&sql[start..i]));
+            }
+            b if b.is_ascii_digit() => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                tokens.push(Token::Literal);
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            b => {
+                tokens.push(Token::Punct(b));
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Byte offset where the inline data of `INSERT ... FORMAT <name>` starts.
+pub(crate) fn insert_data_offset(query: &str) -> Option<usize> {
+    let tokens = tokenize_head(query);
+    if !tokens.first().is_some_and(|t| t.is_word("INSERT")) {
+        return None;
+    }
+
+    let format = match tokens.last() {
+        Some(Token::Word(word)) if word.eq_ignore_ascii_case("FORMAT") => word,
+        _ => return None,
+    };
+    let rest = &query[offset_in(query, format) + format.len()..];
+    let name_start = rest.len() - rest.trim_start().len();
+    let name_len = rest[name_start..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len() - name_start);
+
+    Some(query.len() - rest.len() + name_start + name_len)
+}
+
+/// End of the `VALUES` keyword of an `INSERT ... VALUES` statement.
+pub(crate) fn values_offset(query: &str) -> Option<usize> {
+    let tokens = tokenize_head(query);
+    if !tokens.first().is_some_and(|t| t.is_word("INSERT")) {
+        return None;
+    }
+
+    let mut depth = 0i32;
+    for token in &tokens {
+        match token {
+            Token::Punct(b'(') => depth += 1,
+            Token::Punct(b')') => depth -= 1,
+            Token::Word(word) if depth == 0 && word.eq_ignore_ascii_case("SELECT") => return None,
+            Token::Word(word) if depth == 0 && word.eq_ignore_ascii_case("VALUES") => {
+                return Some(offset_in(query, word) + word.len());
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Tokens of the statement head, up to the first `VALUES`/`FORMAT`/`SELECT` keyword.
+fn tokenize_head(query: &str) -> Vec<Token<'_>> {
+    let bytes = query.as_bytes();
+    let is_word_byte = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let head_end = (0..bytes.len())
+        .find(|&i| {
+            let Some(word) = query.get(i..i + 6) else {
+                return false;
+            };
+            ["VALUES", "FORMAT", "SELECT"]
+                .iter()
+                .any(|k| word.eq_ignore_ascii_case(k))
+                && !(i > 0 && is_word_byte(&bytes[i - 1]))
+                && !bytes.get(i + 6).is_some_and(is_word_byte)
+        })
+        .map_or(query.len(), |i| i + 6);
+    tokenize(&query[..head_end])
+}
+
+fn offset_in(query: &str, word: &str) -> usize {
+    word.as_ptr() as usize - query.as_ptr() as usize
+}
+
+/// Adds `ctes` ("name AS (...), ...") to the WITH clause of `query`, creating one if needed.
+///
+/// For `INSERT ... SELECT` the clause goes to the SELECT part. `None` for statements without
+/// a SELECT part, such as `ALTER` or `INSERT ... VALUES`.
+pub(crate) fn prepend_ctes(query: &str, ctes: &str) -> Option<String> {
+    let tokens = tokenize(query);
+    let offset = |word: &str| offset_in(query, word);
+
+    match with_clause_start(&tokens)? {
+        Token::Word(word) if word.eq_ignore_ascii_case("WITH") => {
+            let end = offset(word) + word.len();
+            Some(format!("{} {},{}", &query[..end], ctes, &query[end..]))
+        }
+        Token::Word(word) => {
+            let at = offset(word);
+            Some(format!("{}WITH {} {}", &query[..at], ctes, &query[at..]))
+        }
+        _ => Some(format!("WITH {} {}", ctes, query)),
+    }
+}
+
+/// Whether `prepend_ctes` can add a WITH clause to `query`.
+pub(crate) fn accepts_ctes(query: &str) -> bool {
+    with_clause_start(&tokenize(query)).is_some()
+}
+
+/// The `WITH` or `SELECT` a WITH clause goes before, or the `(` of a parenthesized select.
+fn with_clause_start<'t, 'a>(tokens: &'t [Token<'a>]) -> Option<&'t Token<'a>> {
+    let first = tokens.first()?;
+    if !first.is_word("INSERT") {
+        let takes_clause =
+            first.is_word("WITH") || first.is_word("SELECT") || *first == Token::Punct(b'(');
+        return takes_clause.then_some(first);
+    }
+
+    let mut depth = 0i32;
+    tokens.iter().find(|t| match t {
+        Token::Punct(b'(') => {
+            depth += 1;
+            false
+        }
+        Token::Punct(b')') => {
+            depth -= 1;
+            false
+        }
+        _ => depth == 0 && (t.is_word("SELECT") || t.is_word("WITH")),
+    })
+}
+
+/// For `FORMAT <name>` ending at `pos`, returns where the inline data ends, unless the
+/// format is `Values` or there is no inline data.
+fn inline_data_end(script: &str, pos: usize) -> Option<usize> {
+    let bytes = script.as_bytes();
+    let mut i = pos;
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() && bytes[i] != b'\n' {
+        i += 1;
+    }
+
+    let name_start = i;
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        i += 1;
+    }
+    let name = &script[name_start..i];
+    if name.is_empty() || name.eq_ignore_ascii_case("Values") {
+        return None;
+    }
+
+    while i < bytes.len() && bytes[i] != b'\n' && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    if i >= bytes.len() || bytes[i] == b'\n' || bytes[i] == b';' {
+        return None;
+    }
+
+    Some(
+        script[i..]
+            .find('\n')
+            .map_or(bytes.len(), |offset| i + offset),
+    )
+}
+
+pub(crate) fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => {
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+pub(crate) fn skip_line(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |offset| start + offset + 1)
+}
+
+pub(crate) fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// Words that can follow a table reference without being its alias.
+pub(crate) const CLAUSE_KEYWORDS: &[&str] = &[
+    "ANTI",
+    "ANY",
+    "ARRAY",
+    "ASOF",
+    "CROSS",
+    "EXCEPT",
+    "FINAL",
+    "FORMAT",
+    "FULL",
+    "GLOBAL",
+    "GROUP",
+    "HAVING",
+    "INNER",
+    "INTERSECT",
+    "INTO",
+    "JOIN",
+    "LEFT",
+    "LIMIT",
+    "ON",
+    "ORDER",
+    "OUTER",
+    "PASTE",
+    "PREWHERE",
+    "QUALIFY",
+    "RIGHT",
+    "SAMPLE",
+    "SELECT",
+    "SEMI",
+    "SETTINGS",
+    "UNION",
+    "USING",
+    "VALUES",
+    "WHERE",
+    "WINDOW",
+];
+
+/// Names defined by `WITH name AS (...)`.
+pub(crate) fn cte_names<'a>(tokens: &[Token<'a>]) -> HashSet<&'a str> {
+    tokens
+        .windows(4)
+        .filter(|w| {
+            (w[0].is_word("WITH") || w[0] == Token::Punct(b','))
+                && w[2].is_word("AS")
+                && w[3] == Token::Punct(b'(')
+        })
+        .filter_map(|w| w[1].name())
+        .collect()
+}
+
+/// Names defined by `WITH name AS (...)`, each with the token range it is visible in: from
+/// its `WITH` to the end of the enclosing parentheses, or of the statement.
+pub(crate) fn cte_scopes<'a>(tokens: &[Token<'a>]) -> Vec<(&'a str, std::ops::Range<usize>)> {
+    let mut closing = vec![tokens.len(); tokens.len()];
+    let mut open = Vec::new();
+    let mut enclosing = vec![None; tokens.len()];
+    for (i, token) in tokens.iter().enumerate() {
+        enclosing[i] = open.last().copied();
+        match token {
+            Token::Punct(b'(') => open.push(i),
+            Token::Punct(b')') => {
+                if let Some(start) = open.pop() {
+                    closing[start] = i;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut scopes = Vec::new();
+    for (with, _) in tokens.iter().enumerate().filter(|(_, t)| t.is_word("WITH")) {
+        let end = enclosing[with].map_or(tokens.len(), |start| closing[start]);
+        let mut i = with + 1;
+        while let Some(name) = tokens.get(i).and_then(Token::name) {
+            if !tokens.get(i + 1).is_some_and(|t| t.is_word("AS"))
+                || tokens.get(i + 2) != Some(&Token::Punct(b'('))
+            {
+                break;
+            }
+            scopes.push((name, with..end));
+            i = closing[i + 2] + 1;
+            if tokens.get(i) != Some(&Token::Punct(b',')) {
+                break;
+            }
+            i += 1;
+        }
+    }
+    scopes
+}
+
+/// Byte range of a `Word` or `Quoted` token of `sql`, including the quotes.
+pub(crate) fn token_span(sql: &str, token: &Token) -> Option<std::ops::Range<usize>> {
+    let name = token.name()?;
+    let start = name.as_ptr() as usize - sql.as_ptr() as usize;
+    match token {
+        Token::Quoted(_) => Some(start - 1..start + name.len() + 1),
+        _ => Some(start..start + name.len()),
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::error::Error;
+use crate::guard::StatementKind;
+use crate::intercept::Statement;
+use crate::intercept::StatementInterceptor;
+use crate::profile::TableProfile;
+use crate::schema::Schema;
+use crate::session::Session;
+use crate::sql::split_statements;
+use crate::sql::tokenize;
+
+/// Schema and profile of a table, see `Session::stats`.
+#[derive(Debug, Clone)]
+pub struct TableStats {
+    pub schema: Schema,
+    pub profile: TableProfile,
+    pub computed_at: SystemTime,
+    /// Whether the stats came from the cache.
+    pub cached: bool,
+}
+
+impl TableStats {
+    pub fn age(&self) -> Duration {
+        self.computed_at.elapsed().unwrap_or_default()
+    }
+}
+
+/// Stats per table name, dropped when a statement that may change the table runs.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCache {
+    tables: Mutex<HashMap<String, TableStats>>,
+}
+
+/// Invalidates cached stats of the tables a statement names, unless it only reads.
+struct StatsInvalidator(Arc<StatsCache>);
+
+impl StatementInterceptor for StatsInvalidator {
+    fn intercept(&self, statement: &mut Statement) {
+        for sql in split_statements(&statement.sql) {
+            let read_only = matches!(
+                StatementKind::of(sql),
+                StatementKind::Select
+                    | StatementKind::Show
+                    | StatementKind::Describe
+                    | StatementKind::Explain
+            );
+            if !read_only {
+                self.0.invalidate(sql);
+            }
+        }
+    }
+}
+
+impl StatsCache {
+    fn get(&self, table: &str) -> Option<TableStats> {
+        let tables = self.tables.lock().unwrap_or_else(|e| e.into_inner());
+        tables.get(table).cloned()
+    }
+
+    fn insert(&self, table: &str, stats: TableStats) {
+        let mut tables = self.tables.lock().unwrap_or_else(|e| e.into_inner());
+        tables.insert(table.to_string(), stats);
+    }
+
+    /// Drops tables whose (unqualified) name appears as an identifier in `sql`.
+    fn invalidate(&self, sql: &str) {
+        let mut tables = self.tables.lock().unwrap_or_else(|e| e.into_inner());
+        if tables.is_empty() {
+            return;
+        }
+
+        let names: Vec<&str> = tokenize(sql).iter().filter_map(|t| t.name()).collect();
+        tables.retain(|table, _| {
+            let name = table.rsplit('.').next().unwrap_or(table);
+            !names.contains(&name)
+        });
+    }
+}
+
+impl Session {
+    /// Caches `stats` until a statement run through this session may have changed the table:
+    /// any statement other than `SELECT`, `SHOW`, `DESCRIBE` and `EXPLAIN` naming it.
+    ///
+    /// Changes made outside the session, e.g. by another process on the same data path, are
+    /// not noticed; check `TableStats::age`.
+    pub fn with_stats_cache(mut self) -> Self {
+        let cache = Arc::new(StatsCache::default());
+        self.stats_cache = Some(cache.clone());
+        self.with_interceptor(StatsInvalidator(cache))
+    }
+
+    /// Schema and full profile of `table`, from the cache when enabled and still valid.
+    pub fn stats(&self, table: &str) -> Result<TableStats, Error> {
+        if let Some(stats) = self.stats_cache.as_ref().and_then(|cache| cache.get(table)) {
+            return Ok(TableStats {
+                cached: true,
+                ..stats
+            });
+        }
+
+        let handle = self.table(table);
+        let stats = TableStats {
+            schema: handle.schema()?,
+            profile: handle.profile(1.0)?,
+            computed_at: SystemTime::now(),
+            cached: false,
+        };
+        if let Some(cache) = &self.stats_cache {
+            cache.insert(table, stats.clone());
+        }
+
+        Ok(stats)
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::Error;
+use crate::session::Session;
+
+/// Engine codes of unknown table and column errors.
+const UNKNOWN_IDENTIFIER: u32 = 47;
+const UNKNOWN_TABLE: u32 = 60;
+
+/// Suggestions attached to an error at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+impl Session {
+    /// Turns unknown table and column errors into `Error::UnknownName` with the closest
+    /// existing names, see `SessionBuilder::with_identifier_suggestions`.
+    pub(crate) fn add_suggestions(&self, error: Error) -> Error {
+        let (code, message) = match (error.code(), &error) {
+            (Some(code), Error::QueryError(message))
+                if code == UNKNOWN_IDENTIFIER || code == UNKNOWN_TABLE =>
+            {
+                (code, message)
+            }
+            _ => return error,
+        };
+        let Some(name) = unknown_name(message) else {
+            return error;
+        };
+
+        let query = if code == UNKNOWN_TABLE {
+            // tables of the current database under both names, to match either kind of message
+            "SELECT arrayJoin(if(database = currentDatabase(), [name, database || '.' || name], \
+             [database || '.' || name])) FROM system.tables \
+             WHERE database NOT IN ('system', 'INFORMATION_SCHEMA', 'information_schema')"
+        } else {
+            "SELECT DISTINCT name FROM system.columns WHERE database = currentDatabase()"
+        };
+        // the original error matters more than a failure to look up suggestions
+        let Ok(rows) = self.query_rows(query) else {
+            return error;
+        };
+
+        let suggestions = closest(name, rows.into_iter().flatten());
+        if suggestions.is_empty() {
+            return error;
+        }
+        Error::UnknownName {
+            message: message.clone(),
+            suggestions,
+        }
+    }
+}
+
+/// Name quoted in an unknown table or identifier message.
+fn unknown_name(message: &str) -> Option<&str> {
+    for marker in ["identifier ", "Missing columns: ", "Table "] {
+        let Some(start) = message.find(marker) else {
+            continue;
+        };
+        let rest = &message[start + marker.len()..];
+        let name = match rest.chars().next()? {
+            quote @ ('\'' | '`') => rest[1..].split(quote).next(),
+            _ => rest.split(' ').next(),
+        };
+        if let Some(name) = name.filter(|name| !name.is_empty()) {
+            return Some(name);
+        }
+    }
+    None
+}
+
+/// Candidates within an edit distance of a third of `name`'s length (without a database
+/// prefix), closest first.
+fn closest(name: &str, candidates: impl Iterator<Item = String>) -> Vec<String> {
+    let unqualified = name.rsplit('.').next().unwrap_or(name);
+    let max_distance = (unqualified.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, String)> = candidates
+        .filter(|candidate| candidate != name)
+        .filter_map(|candidate| {
+            let distance = levenshtein(&name.to_lowercase(), &candidate.to_lowercase());
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup();
+
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> impl Iterator<Item = String> {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("events", "events"), 0);
+        assert_eq!(levenshtein("evnts", "events"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("héllo", "hello"), 1);
+    }
+
+    #[test]
+    fn threshold_and_order() {
+        // a third of the name's length, at least one edit
+        assert_eq!(closest("evnts", names(&["events", "orders"])), ["events"]);
+        assert!(closest("ab", names(&["xy"])).is_empty());
+        assert_eq!(closest("ab", names(&["ac"])), ["ac"]);
+        assert!(closest("user_idx", names(&["order_id"])).is_empty());
+
+        // closest first, ties alphabetically, the name itself and duplicates dropped
+        assert_eq!(
+            closest(
+                "user_id",
+                names(&["users_id", "user_ids", "user_i", "user_id", "user_ids"])
+            ),
+            ["user_i", "user_ids", "users_id"]
+        );
+        assert_eq!(closest("Events", names(&["events"])), ["events"]);
+        assert_eq!(
+            closest(
+                "abcdefghi",
+                names(&["abcdefghx", "abcdefgxx", "abcdefghy", "abcdefghz"])
+            ),
+            ["abcdefghx", "abcdefghy", "abcdefghz"]
+        );
+    }
+
+    #[test]
+    fn engine_messages() {
+        let messages = [
+            (
+                "Code: 60. DB::Exception: Unknown table expression identifier 'evnts' in scope \
+                 SELECT * FROM evnts. (UNKNOWN_TABLE)",
+                "evnts",
+            ),
+            (
+                "Code: 60. DB::Exception: Table default.evnts does not exist. (UNKNOWN_TABLE)",
+                "default.evnts",
+            ),
+            (
+                "Code: 47. DB::Exception: Unknown expression identifier `usr_id` in scope \
+                 SELECT usr_id FROM users. (UNKNOWN_IDENTIFIER)",
+                "usr_id",
+            ),
+            (
+                "Code: 47. DB::Exception: Missing columns: 'usr_id' while processing query: \
+                 'SELECT usr_id FROM users'. (UNKNOWN_IDENTIFIER)",
+                "usr_id",
+            ),
+        ];
+        for (message, name) in messages {
+            assert_eq!(unknown_name(message), Some(name), "{}", message);
+        }
+        assert_eq!(unknown_name("Code: 62. DB::Exception: Syntax error"), None);
+
+        let tables = names(&["events", "default.events", "users", "default.users"]);
+        assert_eq!(
+            closest(unknown_name(messages[1].0).unwrap(), tables),
+            ["default.events"]
+        );
+    }
+}
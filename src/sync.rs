@@ -0,0 +1,338 @@
+use std::time::Duration;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::remote::RemoteConfig;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Incremental copy of a remote table into the session.
+///
+/// Each run pulls the rows whose watermark column is above the local maximum. Rows arriving
+/// upstream with a watermark at or below it are not pulled; `SyncReport` reports them as a
+/// gap by comparing row counts up to the watermark.
+pub struct TableSync<'a> {
+    session: &'a Session,
+    remote: RemoteConfig,
+    remote_table: String,
+    local_table: String,
+    watermark: String,
+    batch_rows: u64,
+}
+
+/// Outcome of `TableSync::sync_once`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    pub rows_pulled: u64,
+    /// Local maximum of the watermark column after the run, `None` while the table is empty.
+    pub watermark: Option<String>,
+    /// Rows upstream at or below the watermark that the local table doesn't have.
+    pub missing_rows: u64,
+    /// Local rows at or below the watermark that are gone upstream, e.g. after deletes.
+    pub extra_rows: u64,
+}
+
+/// What `Session::push_to_remote` uploads per call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushBatch {
+    remote_table: String,
+    order_by: String,
+    max_rows: u64,
+}
+
+impl PushBatch {
+    /// Uploads to `remote_table` the oldest rows by `order_by`, an insertion time or an
+    /// increasing id.
+    pub fn new(remote_table: &str, order_by: &str) -> Self {
+        Self {
+            remote_table: remote_table.to_string(),
+            order_by: order_by.to_string(),
+            max_rows: 100_000,
+        }
+    }
+
+    /// Rows per upload, 100,000 by default. Rows tied with the last one are included.
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = rows.max(1);
+        self
+    }
+}
+
+impl PushBatch {
+    /// Largest ordering value of the next batch of `table`, `None` when the table is empty.
+    pub(crate) fn next_cutoff(
+        &self,
+        session: &Session,
+        table: &str,
+    ) -> Result<Option<String>, Error> {
+        let order_by = quote_identifier(&self.order_by);
+        let rows = session.query_rows(&format!(
+            "SELECT count(), toString(max({order_by})) FROM \
+             (SELECT {order_by} FROM {} ORDER BY {order_by} LIMIT {})",
+            quote_qualified(table),
+            self.max_rows
+        ))?;
+        let row = rows.into_iter().next().unwrap_or_default();
+        let [count, cutoff] =
+            <[String; 2]>::try_from(row).map_err(|row| unexpected_columns(2, row.len()))?;
+
+        Ok((parse_u64(&count)? > 0).then_some(cutoff))
+    }
+
+    /// Condition selecting the rows of the batch ending at `cutoff`.
+    pub(crate) fn filter(&self, cutoff: &str) -> String {
+        format!(
+            "{} <= {}",
+            quote_identifier(&self.order_by),
+            quote_string(cutoff)
+        )
+    }
+
+    pub(crate) fn remote_table(&self) -> &str {
+        &self.remote_table
+    }
+}
+
+impl Session {
+    /// Uploads the oldest rows of `table` to the remote server and deletes them locally once
+    /// the upload succeeded. Returns the number of rows pushed, 0 when the table is empty.
+    ///
+    /// Rows are selected up to a cutoff value of the batch's ordering column; rows inserted
+    /// locally during the upload with a value at or below the cutoff are deleted unsent.
+    pub fn push_to_remote(
+        &self,
+        table: &str,
+        remote: &RemoteConfig,
+        batch: &PushBatch,
+    ) -> Result<u64, Error> {
+        let Some(cutoff) = batch.next_cutoff(self, table)? else {
+            return Ok(0);
+        };
+        let filter = batch.filter(&cutoff);
+        let pushed = count_where(self, table, &filter)?;
+
+        push_rows(self, table, remote, batch, &filter, None)?;
+        self.execute(
+            &format!("DELETE FROM {} WHERE {}", quote_qualified(table), filter),
+            None,
+        )?;
+
+        Ok(pushed)
+    }
+}
+
+/// `INSERT INTO FUNCTION remote(...) SELECT * FROM <table> WHERE <filter>`.
+pub(crate) fn push_rows(
+    session: &Session,
+    table: &str,
+    remote: &RemoteConfig,
+    batch: &PushBatch,
+    filter: &str,
+    query_args: Option<&[Arg]>,
+) -> Result<(), Error> {
+    session.execute(
+        &format!(
+            "INSERT INTO FUNCTION {} SELECT * FROM {} WHERE {}",
+            remote.table_function(batch.remote_table()),
+            quote_qualified(table),
+            filter
+        ),
+        query_args,
+    )?;
+
+    Ok(())
+}
+
+pub(crate) fn count_where(session: &Session, table: &str, filter: &str) -> Result<u64, Error> {
+    let rows = session.query_rows(&format!(
+        "SELECT count() FROM {} WHERE {}",
+        quote_qualified(table),
+        filter
+    ))?;
+
+    match rows.into_iter().flatten().next() {
+        Some(count) => parse_u64(&count),
+        None => Err(Error::InvalidData("empty count() result".to_string())),
+    }
+}
+
+impl SyncReport {
+    /// Whether both sides hold the same number of rows up to the watermark.
+    pub fn is_consistent(&self) -> bool {
+        self.missing_rows == 0 && self.extra_rows == 0
+    }
+}
+
+impl<'a> TableSync<'a> {
+    /// Syncs `remote_table` into `local_table`, ordered by `watermark` (e.g. an insertion
+    /// time or an increasing id). The local table is created with the remote structure.
+    pub fn new(
+        session: &'a Session,
+        remote: RemoteConfig,
+        remote_table: &str,
+        local_table: &str,
+        watermark: &str,
+    ) -> Self {
+        Self {
+            session,
+            remote,
+            remote_table: remote_table.to_string(),
+            local_table: local_table.to_string(),
+            watermark: watermark.to_string(),
+            batch_rows: 1_000_000,
+        }
+    }
+
+    /// Rows pulled per run, 1,000,000 by default. Rows sharing the watermark of the last one
+    /// are pulled in the same run, so a run may go over it.
+    pub fn with_batch_rows(mut self, rows: u64) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    pub fn sync_once(&self) -> Result<SyncReport, Error> {
+        self.sync_from(&self.remote.table_function(&self.remote_table))
+    }
+
+    /// `sync_once` reading from `source`, a table or table function.
+    fn sync_from(&self, source: &str) -> Result<SyncReport, Error> {
+        let local = quote_qualified(&self.local_table);
+        let watermark = quote_identifier(&self.watermark);
+
+        self.session.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} ENGINE = MergeTree ORDER BY {} \
+                 AS SELECT * FROM {} WHERE 0",
+                local, watermark, source
+            ),
+            None,
+        )?;
+
+        let (before, rows_before) = self.local_state()?;
+        let filter = match &before {
+            Some(value) => format!("WHERE {} > {}", watermark, quote_string(value)),
+            None => String::new(),
+        };
+        // the next run only pulls rows above the local maximum, so ties of the last row must
+        // come along in this one
+        self.session.execute(
+            &format!(
+                "INSERT INTO {} SELECT * FROM {} {} ORDER BY {} LIMIT {} WITH TIES",
+                local, source, filter, watermark, self.batch_rows
+            ),
+            None,
+        )?;
+
+        let (after, local_rows) = self.local_state()?;
+        let (missing_rows, extra_rows) = match &after {
+            Some(value) => {
+                // every local row is at or below the local maximum
+                let remote_rows = self.count(source, &watermark, value)?;
+                (
+                    remote_rows.saturating_sub(local_rows),
+                    local_rows.saturating_sub(remote_rows),
+                )
+            }
+            None => (0, 0),
+        };
+
+        Ok(SyncReport {
+            rows_pulled: local_rows.saturating_sub(rows_before),
+            watermark: after,
+            missing_rows,
+            extra_rows,
+        })
+    }
+
+    /// Runs `sync_once` every `interval` until `on_report` returns false.
+    ///
+    /// Failed runs are passed to `on_report` and retried on the next interval.
+    pub fn run(
+        &self,
+        interval: Duration,
+        mut on_report: impl FnMut(Result<SyncReport, Error>) -> bool,
+    ) {
+        while on_report(self.sync_once()) {
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Local maximum of the watermark and row count.
+    fn local_state(&self) -> Result<(Option<String>, u64), Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT count(), toString(max({})) FROM {}",
+            quote_identifier(&self.watermark),
+            quote_qualified(&self.local_table)
+        ))?;
+        let row = rows.into_iter().next().unwrap_or_default();
+        let [count, max] =
+            <[String; 2]>::try_from(row).map_err(|row| unexpected_columns(2, row.len()))?;
+
+        let count = parse_u64(&count)?;
+        Ok(((count > 0).then_some(max), count))
+    }
+
+    fn count(&self, table: &str, watermark: &str, value: &str) -> Result<u64, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT count() FROM {} WHERE {} <= {}",
+            table,
+            watermark,
+            quote_string(value)
+        ))?;
+
+        match rows.into_iter().flatten().next() {
+            Some(count) => parse_u64(&count),
+            None => Err(Error::InvalidData("empty count() result".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionBuilder;
+
+    #[test]
+    fn ties_at_the_batch_boundary() {
+        let session = SessionBuilder::new()
+            .with_data_path("/tmp/chdb-sync-ties")
+            .with_auto_cleanup(true)
+            .build()
+            .unwrap();
+        session
+            .execute(
+                "CREATE TABLE upstream (wm UInt64, v String) ENGINE = MergeTree ORDER BY wm",
+                None,
+            )
+            .unwrap();
+        session
+            .execute(
+                "INSERT INTO upstream VALUES (1, 'a'), (2, 'b'), (2, 'c'), (2, 'd'), (3, 'e')",
+                None,
+            )
+            .unwrap();
+
+        let sync = TableSync::new(
+            &session,
+            RemoteConfig::new("unused:9000"),
+            "upstream",
+            "local",
+            "wm",
+        )
+        .with_batch_rows(2);
+
+        let first = sync.sync_from("upstream").unwrap();
+        assert_eq!(first.rows_pulled, 4);
+        assert_eq!(first.watermark.as_deref(), Some("2"));
+        assert!(first.is_consistent());
+
+        let second = sync.sync_from("upstream").unwrap();
+        assert_eq!(second.rows_pulled, 1);
+        assert_eq!(second.watermark.as_deref(), Some("3"));
+        assert!(second.is_consistent());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+use crate::sql::quote_qualified;
+use crate::sql::quote_string;
+use crate::tsv::parse_u64;
+use crate::tsv::unexpected_columns;
+
+/// Handle to a table inside a session.
+pub struct Table<'a> {
+    session: &'a Session,
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    None,
+    Lz4,
+    Lz4Hc(u8),
+    Zstd(u8),
+    Delta(u8),
+    DoubleDelta,
+    Gorilla,
+    T64,
+}
+
+#[derive(Debug, Clone)]
+struct ColumnDef {
+    name: String,
+    type_name: String,
+    codecs: Option<Vec<Codec>>,
+}
+
+/// Builder for `CREATE TABLE` statements.
+#[derive(Debug, Clone)]
+pub struct TableBuilder {
+    name: String,
+    columns: Vec<ColumnDef>,
+    default_codecs: Vec<Codec>,
+    engine: String,
+    order_by: String,
+    partition_by: Option<String>,
+    if_not_exists: bool,
+}
+
+/// Per-column on-disk sizes as reported by `system.columns`.
+#[derive(Debug, Clone)]
+pub struct ColumnCompression {
+    pub name: String,
+    pub type_name: String,
+    pub codec: String,
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+}
+
+impl<'a> Table<'a> {
+    pub(crate) fn new(session: &'a Session, name: &str) -> Self {
+        Self {
+            session,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn session(&self) -> &'a Session {
+        self.session
+    }
+
+    pub fn compression_stats(&self) -> Result<Vec<ColumnCompression>, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT name, type, compression_codec, data_compressed_bytes, data_uncompressed_bytes \
+             FROM system.columns WHERE {} ORDER BY position",
+            self.system_filter()
+        ))?;
+
+        rows.into_iter()
+            .map(|row| match <[String; 5]>::try_from(row) {
+                Ok([name, type_name, codec, compressed, uncompressed]) => Ok(ColumnCompression {
+                    name,
+                    type_name,
+                    codec,
+                    compressed_bytes: parse_u64(&compressed)?,
+                    uncompressed_bytes: parse_u64(&uncompressed)?,
+                }),
+                Err(row) => Err(unexpected_columns(5, row.len())),
+            })
+            .collect()
+    }
+
+    /// Row count from the active parts' metadata, without reading data. Tables without parts
+    /// (views, Memory, empty MergeTrees) fall back to `count()`.
+    ///
+    /// Rows deleted with lightweight `DELETE` are still counted until the parts are merged.
+    pub fn estimated_rows(&self) -> Result<u64, Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT count(), sum(rows) FROM system.parts WHERE active AND {}",
+            self.system_filter()
+        ))?;
+        let row = rows.into_iter().next().unwrap_or_default();
+        let [parts, estimate] =
+            <[String; 2]>::try_from(row).map_err(|row| unexpected_columns(2, row.len()))?;
+        if parse_u64(&parts)? > 0 {
+            return parse_u64(&estimate);
+        }
+
+        let rows = self
+            .session
+            .query_rows(&format!("SELECT count() FROM {}", self.quoted_name()))?;
+        match rows.into_iter().flatten().next() {
+            Some(count) => parse_u64(&count),
+            None => Err(Error::InvalidData("empty count() result".to_string())),
+        }
+    }
+
+    /// Sorting and partition key expressions, empty when the table has none.
+    pub(crate) fn keys(&self) -> Result<(String, String), Error> {
+        let rows = self.session.query_rows(&format!(
+            "SELECT sorting_key, partition_key FROM system.tables WHERE {}",
+            self.system_filter()
+        ))?;
+
+        match rows.into_iter().next() {
+            Some(row) => match <[String; 2]>::try_from(row) {
+                Ok([sorting_key, partition_key]) => Ok((sorting_key, partition_key)),
+                Err(row) => Err(unexpected_columns(2, row.len())),
+            },
+            None => Ok((String::new(), String::new())),
+        }
+    }
+
+    /// Runs `ALTER TABLE <name> <command>`.
+    pub(crate) fn alter(&self, command: &str) -> Result<(), Error> {
+        self.session.execute(
+            &format!("ALTER TABLE {} {}", self.quoted_name(), command),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    pub(crate) fn quoted_name(&self) -> String {
+        quote_qualified(&self.name)
+    }
+
+    /// `database = ... AND table = ...` condition for querying system tables.
+    pub(crate) fn system_filter(&self) -> String {
+        match self.name.split_once('.') {
+            Some((database, table)) => format!(
+                "database = {} AND table = {}",
+                quote_string(database),
+                quote_string(table)
+            ),
+            None => format!(
+                "database = currentDatabase() AND table = {}",
+                quote_string(&self.name)
+            ),
+        }
+    }
+}
+
+impl ColumnCompression {
+    /// Uncompressed to compressed size ratio, 0 for empty columns.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            return 0.0;
+        }
+        self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+    }
+}
+
+impl Codec {
+    pub fn to_sql(self) -> String {
+        match self {
+            Self::None => "NONE".to_string(),
+            Self::Lz4 => "LZ4".to_string(),
+            Self::Lz4Hc(level) => format!("LZ4HC({})", level),
+            Self::Zstd(level) => format!("ZSTD({})", level),
+            Self::Delta(bytes) => format!("Delta({})", bytes),
+            Self::DoubleDelta => "DoubleDelta".to_string(),
+            Self::Gorilla => "Gorilla".to_string(),
+            Self::T64 => "T64".to_string(),
+        }
+    }
+}
+
+impl TableBuilder {
+    /// MergeTree table ordered by `tuple()` unless configured otherwise.
+    ///
+    /// ORDER BY is only emitted for MergeTree family engines.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: Vec::new(),
+            default_codecs: Vec::new(),
+            engine: "MergeTree".to_string(),
+            order_by: "tuple()".to_string(),
+            partition_by: None,
+            if_not_exists: false,
+        }
+    }
+
+    pub fn with_column(mut self, name: &str, type_name: &str) -> Self {
+        self.columns.push(ColumnDef {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            codecs: None,
+        });
+        self
+    }
+
+    /// Column with an explicit codec chain, e.g. `[Codec::Delta(4), Codec::Zstd(3)]`.
+    pub fn with_column_codec(mut self, name: &str, type_name: &str, codecs: &[Codec]) -> Self {
+        self.columns.push(ColumnDef {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            codecs: Some(codecs.to_vec()),
+        });
+        self
+    }
+
+    /// Codec chain for columns added without one.
+    pub fn with_default_codec(mut self, codecs: &[Codec]) -> Self {
+        self.default_codecs = codecs.to_vec();
+        self
+    }
+
+    pub fn with_engine(mut self, engine: &str) -> Self {
+        self.engine = engine.to_string();
+        self
+    }
+
+    pub fn with_order_by(mut self, expr: &str) -> Self {
+        self.order_by = expr.to_string();
+        self
+    }
+
+    pub fn with_partition_by(mut self, expr: &str) -> Self {
+        self.partition_by = Some(expr.to_string());
+        self
+    }
+
+    pub fn with_if_not_exists(mut self, value: bool) -> Self {
+        self.if_not_exists = value;
+        self
+    }
+
+    pub fn to_sql(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let mut def = format!("{} {}", quote_identifier(&column.name), column.type_name);
+                let codecs = column.codecs.as_ref().unwrap_or(&self.default_codecs);
+                if !codecs.is_empty() {
+                    let codecs: Vec<String> = codecs.iter().map(|c| c.to_sql()).collect();
+                    def.push_str(&format!(" CODEC({})", codecs.join(", ")));
+                }
+                def
+            })
+            .collect();
+
+        let mut sql = format!(
+            "CREATE TABLE {}{} ({}) ENGINE = {}",
+            if self.if_not_exists {
+                "IF NOT EXISTS "
+            } else {
+                ""
+            },
+            quote_qualified(&self.name),
+            columns.join(", "),
+            self.engine
+        );
+        if let Some(partition_by) = &self.partition_by {
+            sql.push_str(&format!(" PARTITION BY {}", partition_by));
+        }
+        if self.engine.contains("MergeTree") {
+            sql.push_str(&format!(" ORDER BY {}", self.order_by));
+        }
+        sql
+    }
+
+    pub fn build(self, session: &Session) -> Result<Table<'_>, Error> {
+        session.execute(&self.to_sql(), None)?;
+        Ok(Table::new(session, &self.name))
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::error::Error;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Uniquely named file in the system temp directory, removed on drop.
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Reserves a name, the file itself is created by whoever writes it.
+    pub(crate) fn new(extension: &str) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let name = format!(
+            "chdb-rust-{}-{}-{}.{}",
+            std::process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            extension
+        );
+
+        Self {
+            path: std::env::temp_dir().join(name),
+        }
+    }
+
+    pub(crate) fn path_str(&self) -> Result<&str, Error> {
+        self.path.to_str().ok_or(Error::PathError)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::guard::Guard;
+use crate::guard::StatementKind;
+use crate::intercept::Statement;
+use crate::intercept::StatementInterceptor;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+use crate::sql::quote_identifier;
+
+/// Runs statements with a tenant's database as the current database.
+///
+/// Usable on its own through `Session::with_interceptor`; `TenantSession` adds the checks
+/// keeping tenants apart.
+#[derive(Debug, Clone)]
+pub struct TenantScope {
+    database: String,
+}
+
+/// Session view of a single tenant.
+///
+/// Each tenant gets its own database, "tenant_<id>". Statements run with it as the current
+/// database and are checked with a `Guard` allowing only that database, so unqualified names
+/// resolve to the tenant's tables and qualified names of other databases are rejected, in
+/// table lists of `RENAME`, `DROP` and `EXCHANGE` too. Engines and dictionary sources that
+/// could name another database (`Merge`, `Buffer`, `Distributed`, `SOURCE(...)`...) and
+/// `CREATE FUNCTION`, whose functions every tenant would see, are rejected as well, see
+/// `Guard`.
+pub struct TenantSession<'a> {
+    session: &'a Session,
+    scope: TenantScope,
+    guard: Guard,
+}
+
+impl TenantScope {
+    pub fn new(tenant_id: &str) -> Result<Self, Error> {
+        let valid = !tenant_id.is_empty()
+            && tenant_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(Error::InvalidData(format!(
+                "invalid tenant id: {}",
+                tenant_id
+            )));
+        }
+
+        Ok(Self {
+            database: format!("tenant_{}", tenant_id),
+        })
+    }
+
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+}
+
+impl StatementInterceptor for TenantScope {
+    fn intercept(&self, statement: &mut Statement) {
+        statement.set("database", &self.database);
+    }
+}
+
+impl<'a> TenantSession<'a> {
+    /// Creates the tenant's database if it doesn't exist.
+    ///
+    /// Tenants may read, write and manage tables of their database, but not run `SET`,
+    /// `SYSTEM` or `USE` statements.
+    pub fn for_tenant(session: &'a Session, tenant_id: &str) -> Result<Self, Error> {
+        let scope = TenantScope::new(tenant_id)?;
+        session.execute(
+            &format!(
+                "CREATE DATABASE IF NOT EXISTS {}",
+                quote_identifier(scope.database())
+            ),
+            None,
+        )?;
+
+        let guard = [
+            StatementKind::Insert,
+            StatementKind::Create,
+            StatementKind::Alter,
+            StatementKind::Drop,
+            StatementKind::Truncate,
+            StatementKind::Rename,
+            StatementKind::Optimize,
+        ]
+        .into_iter()
+        .fold(Guard::read_only(), Guard::with_statement)
+        .with_default_database(scope.database());
+
+        Ok(Self {
+            session,
+            scope,
+            guard,
+        })
+    }
+
+    /// Replaces the guard statements are checked with, its default database is reset to the
+    /// tenant's.
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.guard = guard.with_default_database(self.scope.database());
+        self
+    }
+
+    pub fn database(&self) -> &str {
+        self.scope.database()
+    }
+
+    pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        self.guard.check(query)?;
+
+        let mut statement = Statement {
+            sql: query.to_string(),
+            args: query_args
+                .unwrap_or_default()
+                .iter()
+                .map(|arg| arg.clone().into_owned())
+                .collect(),
+        };
+        self.scope.intercept(&mut statement);
+
+        self.session.execute(&statement.sql, Some(&statement.args))
+    }
+}
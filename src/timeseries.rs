@@ -0,0 +1,232 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::sql::quote_identifier;
+use crate::sql::quote_qualified;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+    /// Value of the earliest row in the bucket.
+    First,
+    /// Value of the latest row in the bucket.
+    Last,
+}
+
+/// Bucket width of `bucket`.
+#[derive(Debug, Clone, Copy)]
+pub enum Granularity {
+    Fixed(Duration),
+    /// The finest round interval that splits `[from, to)` into at most `max_points` buckets.
+    Auto(SystemTime, SystemTime, u64),
+}
+
+/// Round bucket widths `Granularity::Auto` picks from, in seconds with their SQL interval.
+const AUTO_INTERVALS: &[(u64, &str)] = &[
+    (1, "INTERVAL 1 SECOND"),
+    (5, "INTERVAL 5 SECOND"),
+    (10, "INTERVAL 10 SECOND"),
+    (15, "INTERVAL 15 SECOND"),
+    (30, "INTERVAL 30 SECOND"),
+    (60, "INTERVAL 60 SECOND"),
+    (300, "INTERVAL 300 SECOND"),
+    (600, "INTERVAL 600 SECOND"),
+    (900, "INTERVAL 900 SECOND"),
+    (1800, "INTERVAL 1800 SECOND"),
+    (3600, "INTERVAL 3600 SECOND"),
+    (3 * 3600, "INTERVAL 10800 SECOND"),
+    (6 * 3600, "INTERVAL 21600 SECOND"),
+    (12 * 3600, "INTERVAL 43200 SECOND"),
+    (86400, "INTERVAL 1 DAY"),
+    (7 * 86400, "INTERVAL 1 WEEK"),
+    (30 * 86400, "INTERVAL 1 MONTH"),
+    (91 * 86400, "INTERVAL 1 QUARTER"),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Transform {
+    None,
+    Delta,
+    Rate,
+}
+
+impl Granularity {
+    /// SQL interval of a bucket, e.g. `INTERVAL 300 SECOND`.
+    pub fn interval_sql(&self) -> String {
+        match *self {
+            Self::Fixed(interval) => format!("INTERVAL {} SECOND", interval.as_secs().max(1)),
+            Self::Auto(from, to, max_points) => {
+                let span = to.duration_since(from).unwrap_or_default().as_secs();
+                let max_points = max_points.max(1);
+                AUTO_INTERVALS
+                    .iter()
+                    .find(|(secs, _)| span.div_ceil(*secs) <= max_points)
+                    .map_or("INTERVAL 1 YEAR", |(_, sql)| sql)
+                    .to_string()
+            }
+        }
+    }
+}
+
+/// `toStartOfInterval` expression bucketing `column`, for dashboards' `GROUP BY`.
+pub fn bucket(column: &str, granularity: Granularity) -> String {
+    format!(
+        "toStartOfInterval({}, {})",
+        quote_identifier(column),
+        granularity.interval_sql()
+    )
+}
+
+/// Builder for bucketed "value over time" queries.
+///
+/// The generated query returns two columns: `bucket` and `agg_value`.
+#[derive(Debug, Clone)]
+pub struct Downsample {
+    table: String,
+    time_column: String,
+    value_column: String,
+    interval: Duration,
+    aggregation: Aggregation,
+    filter: Option<String>,
+    range: Option<(String, String)>,
+    fill: bool,
+    transform: Transform,
+}
+
+pub fn downsample(
+    table: &str,
+    value_column: &str,
+    interval: Duration,
+    aggregation: Aggregation,
+) -> Downsample {
+    Downsample {
+        table: table.to_string(),
+        time_column: "ts".to_string(),
+        value_column: value_column.to_string(),
+        interval,
+        aggregation,
+        filter: None,
+        range: None,
+        fill: false,
+        transform: Transform::None,
+    }
+}
+
+impl Downsample {
+    /// Column holding the event time, "ts" by default.
+    pub fn with_time_column(mut self, column: &str) -> Self {
+        self.time_column = column.to_string();
+        self
+    }
+
+    /// Additional raw SQL condition.
+    pub fn with_where(mut self, condition: &str) -> Self {
+        self.filter = Some(condition.to_string());
+        self
+    }
+
+    /// Restricts the query to `[from, to)`. Both bounds are SQL expressions.
+    pub fn with_range(mut self, from: &str, to: &str) -> Self {
+        self.range = Some((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Emits a row for every bucket, including empty ones (ORDER BY ... WITH FILL).
+    pub fn with_fill(mut self, value: bool) -> Self {
+        self.fill = value;
+        self
+    }
+
+    /// Difference between consecutive buckets instead of the aggregated value.
+    pub fn delta(mut self) -> Self {
+        self.transform = Transform::Delta;
+        self
+    }
+
+    /// Per-second rate of change between consecutive buckets.
+    pub fn rate(mut self) -> Self {
+        self.transform = Transform::Rate;
+        self
+    }
+
+    pub fn to_sql(&self) -> String {
+        let time = quote_identifier(&self.time_column);
+        let value = quote_identifier(&self.value_column);
+        let interval = self.interval_sql();
+
+        let aggregated = match self.aggregation {
+            Aggregation::Avg => format!("avg({})", value),
+            Aggregation::Sum => format!("sum({})", value),
+            Aggregation::Min => format!("min({})", value),
+            Aggregation::Max => format!("max({})", value),
+            Aggregation::Count => format!("count({})", value),
+            Aggregation::First => format!("argMin({}, {})", value, time),
+            Aggregation::Last => format!("argMax({}, {})", value, time),
+        };
+
+        let mut conditions = Vec::new();
+        if let Some((from, to)) = &self.range {
+            conditions.push(format!("{} >= {}", time, from));
+            conditions.push(format!("{} < {}", time, to));
+        }
+        if let Some(filter) = &self.filter {
+            conditions.push(format!("({})", filter));
+        }
+
+        let mut sql = format!(
+            "SELECT toStartOfInterval({}, {}) AS bucket, {} AS agg_value FROM {}",
+            time,
+            interval,
+            aggregated,
+            quote_qualified(&self.table)
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" GROUP BY bucket");
+
+        let window = "lagInFrame(agg_value, 1, agg_value) OVER (ORDER BY bucket ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)";
+        sql = match self.transform {
+            Transform::None => sql,
+            Transform::Delta => {
+                format!(
+                    "SELECT bucket, agg_value - {} AS agg_value FROM ({})",
+                    window, sql
+                )
+            }
+            Transform::Rate => format!(
+                "SELECT bucket, (agg_value - {}) / {} AS agg_value FROM ({})",
+                window,
+                self.interval_secs(),
+                sql
+            ),
+        };
+
+        sql.push_str(" ORDER BY bucket");
+        if self.fill {
+            sql.push_str(" WITH FILL");
+            if let Some((from, to)) = &self.range {
+                sql.push_str(&format!(
+                    " FROM toStartOfInterval({}, {}) TO {}",
+                    from, interval, to
+                ));
+            }
+            sql.push_str(&format!(" STEP {}", interval));
+        }
+
+        sql
+    }
+
+    fn interval_secs(&self) -> u64 {
+        self.interval.as_secs().max(1)
+    }
+
+    fn interval_sql(&self) -> String {
+        Granularity::Fixed(self.interval).interval_sql()
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::session::Session;
+
+/// Post-processing applied to every result of a session, see `Session::with_transform`.
+pub trait ResultTransform: Send + Sync {
+    fn transform(&self, query: &str, result: QueryResult) -> Result<QueryResult, Error>;
+}
+
+impl<F> ResultTransform for F
+where
+    F: Fn(&str, QueryResult) -> Result<QueryResult, Error> + Send + Sync,
+{
+    fn transform(&self, query: &str, result: QueryResult) -> Result<QueryResult, Error> {
+        self(query, result)
+    }
+}
+
+impl Session {
+    /// Runs `transform` on the result of every `execute`, after the transforms added before.
+    ///
+    /// Helpers built on the session, e.g. `Table::schema`, see the transformed output too, so
+    /// transforms should leave results they don't recognize untouched.
+    pub fn with_transform(mut self, transform: impl ResultTransform + 'static) -> Self {
+        self.transforms.push(Arc::new(transform));
+        self
+    }
+}
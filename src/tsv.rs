@@ -0,0 +1,90 @@
+use crate::error::Error;
+
+/// Parses TabSeparated output into rows of unescaped fields.
+///
+/// NULL values are kept as the literal "\\N".
+pub(crate) fn parse(data: &[u8]) -> Result<Vec<Vec<String>>, Error> {
+    Ok(parse_nullable(data, b"\\N")?
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|field| field.unwrap_or_else(|| "\\N".to_string()))
+                .collect()
+        })
+        .collect())
+}
+
+/// Parses TabSeparated output, mapping fields equal to `null` (before unescaping) to `None`.
+pub(crate) fn parse_nullable(data: &[u8], null: &[u8]) -> Result<Vec<Vec<Option<String>>>, Error> {
+    let mut rows = Vec::new();
+    // "\n" is a row with one empty field, only an empty buffer has no rows
+    if data.is_empty() {
+        return Ok(rows);
+    }
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+
+    for line in data.split(|b| *b == b'\n') {
+        let mut row = Vec::new();
+        for field in line.split(|b| *b == b'\t') {
+            if field == null {
+                row.push(None);
+            } else {
+                row.push(Some(unescape(field)?));
+            }
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn unescape(field: &[u8]) -> Result<String, Error> {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut iter = field.iter();
+
+    while let Some(b) = iter.next() {
+        if *b != b'\\' {
+            bytes.push(*b);
+            continue;
+        }
+
+        match iter.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b'0') => bytes.push(b'\0'),
+            Some(b'b') => bytes.push(0x08),
+            Some(b'f') => bytes.push(0x0c),
+            Some(other) => bytes.push(*other),
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|e| Error::NonUtf8Sequence(e.to_string()))
+}
+
+/// Escapes a field for TabSeparated input.
+pub(crate) fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn parse_u64(value: &str) -> Result<u64, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidData(format!("expected an unsigned integer, got {:?}", value)))
+}
+
+pub(crate) fn unexpected_columns(expected: usize, actual: usize) -> Error {
+    Error::InvalidData(format!("expected {} columns, got {}", expected, actual))
+}
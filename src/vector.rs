@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::arg::Arg;
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::settings::setting;
+use crate::sql::quote_identifier;
+use crate::table::Table;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Distance {
+    Cosine,
+    L2,
+}
+
+impl Distance {
+    pub const fn function_name(self) -> &'static str {
+        match self {
+            Self::Cosine => "cosineDistance",
+            Self::L2 => "L2Distance",
+        }
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Query returning the `k` rows closest to `query_vec`, with an extra `distance` column.
+    pub fn nearest_neighbors_sql(
+        &self,
+        column: &str,
+        query_vec: &[f32],
+        k: usize,
+        distance: Distance,
+    ) -> String {
+        format!(
+            "SELECT *, {}({}, {}) AS distance FROM {} ORDER BY distance LIMIT {}",
+            distance.function_name(),
+            quote_identifier(column),
+            array_literal(query_vec),
+            self.quoted_name(),
+            k
+        )
+    }
+
+    pub fn nearest_neighbors(
+        &self,
+        column: &str,
+        query_vec: &[f32],
+        k: usize,
+        distance: Distance,
+        query_args: Option<&[Arg]>,
+    ) -> Result<QueryResult, Error> {
+        self.session().execute(
+            &self.nearest_neighbors_sql(column, query_vec, k, distance),
+            query_args,
+        )
+    }
+
+    /// Adds an HNSW vector similarity index on an `Array(Float32)` column.
+    ///
+    /// The index type is experimental in ClickHouse; engines that don't support it
+    /// return a query error.
+    pub fn add_vector_index(
+        &self,
+        name: &str,
+        column: &str,
+        distance: Distance,
+    ) -> Result<(), Error> {
+        self.session().execute(
+            &format!(
+                "ALTER TABLE {} ADD INDEX IF NOT EXISTS {} {} TYPE vector_similarity('hnsw', '{}') GRANULARITY 100000000",
+                self.quoted_name(),
+                quote_identifier(name),
+                quote_identifier(column),
+                distance.function_name()
+            ),
+            Some(&[setting("allow_experimental_vector_similarity_index", 1)]),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn drop_vector_index(&self, name: &str) -> Result<(), Error> {
+        self.drop_index(name)
+    }
+}
+
+fn array_literal(values: &[f32]) -> String {
+    let mut literal = String::with_capacity(values.len() * 12 + 2);
+    literal.push('[');
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            literal.push(',');
+        }
+        if value.is_nan() {
+            literal.push_str("nan");
+        } else {
+            write!(literal, "{}", value).unwrap();
+        }
+    }
+    literal.push(']');
+    literal
+}
@@ -0,0 +1,29 @@
+use crate::error::Error;
+use crate::session::Session;
+use crate::sql::quote_qualified;
+
+impl Session {
+    /// Loads the marks and primary index of `tables` into the engine caches, so the first
+    /// real query after opening a session doesn't pay for reading them from disk.
+    pub fn warm_up(&self, tables: &[&str]) -> Result<(), Error> {
+        for table in tables {
+            let table = quote_qualified(table);
+            let prewarmed = ["MARK", "PRIMARY INDEX"].iter().try_for_each(|cache| {
+                self.execute(&format!("SYSTEM PREWARM {} CACHE {}", cache, table), None)
+                    .map(drop)
+            });
+            match prewarmed {
+                Ok(()) => {}
+                // Engines lacking a `SYSTEM PREWARM` command load both on the first read of a part.
+                Err(Error::QueryError(_)) => {
+                    self.execute(
+                        &format!("SELECT * FROM {} LIMIT 1 FORMAT Null", table),
+                        None,
+                    )?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
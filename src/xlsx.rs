@@ -0,0 +1,137 @@
+//! Spreadsheet export of query results.
+
+use std::path::Path;
+
+use rust_xlsxwriter::ExcelDateTime;
+use rust_xlsxwriter::Format;
+use rust_xlsxwriter::Workbook;
+use rust_xlsxwriter::XlsxError;
+
+use crate::error::Error;
+use crate::query_result::QueryResult;
+use crate::tsv;
+
+/// Largest integer an Excel number (`f64`) holds exactly.
+const MAX_EXACT_INTEGER: i64 = 1 << 53;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    DateTime,
+    Text,
+}
+
+impl QueryResult {
+    /// Writes a `TabSeparatedWithNamesAndTypes` result to an XLSX file with a bold header row.
+    ///
+    /// Numbers, booleans, dates and date times become typed cells according to the column
+    /// types; integers Excel can't hold exactly and other types are written as text, NULLs as
+    /// blank cells.
+    pub fn write_xlsx(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut rows = tsv::parse_nullable(self.data_ref(), b"\\N")?.into_iter();
+        let (Some(names), Some(types)) = (rows.next(), rows.next()) else {
+            return Err(Error::InvalidData(
+                "write_xlsx needs TabSeparatedWithNamesAndTypes output".to_string(),
+            ));
+        };
+        let kinds: Vec<CellKind> = types
+            .iter()
+            .map(|type_name| cell_kind(type_name.as_deref().unwrap_or_default()))
+            .collect();
+
+        let header = Format::new().set_bold();
+        let date = Format::new().set_num_format("yyyy-mm-dd");
+        let date_time = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (col, name) in names.iter().enumerate() {
+            sheet
+                .write_string_with_format(
+                    0,
+                    col as u16,
+                    name.as_deref().unwrap_or_default(),
+                    &header,
+                )
+                .map_err(xlsx_error)?;
+        }
+        sheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+
+        for (row, values) in rows.enumerate() {
+            let row = row as u32 + 1;
+            for (col, value) in values.iter().enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                let col = col as u16;
+                let kind = kinds.get(col as usize).copied().unwrap_or(CellKind::Text);
+
+                let written = match kind {
+                    CellKind::Integer => value
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|v| v.abs() <= MAX_EXACT_INTEGER)
+                        .map(|v| sheet.write_number(row, col, v as f64)),
+                    CellKind::Number => value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|v| v.is_finite())
+                        .map(|v| sheet.write_number(row, col, v)),
+                    CellKind::Boolean => value
+                        .parse::<bool>()
+                        .ok()
+                        .map(|v| sheet.write_boolean(row, col, v)),
+                    CellKind::Date | CellKind::DateTime => {
+                        let format = if kind == CellKind::Date {
+                            &date
+                        } else {
+                            &date_time
+                        };
+                        ExcelDateTime::parse_from_str(value)
+                            .ok()
+                            .map(|v| sheet.write_datetime_with_format(row, col, &v, format))
+                    }
+                    CellKind::Text => None,
+                };
+
+                match written {
+                    Some(result) => result.map(|_| ()),
+                    None => sheet.write_string(row, col, value).map(|_| ()),
+                }
+                .map_err(xlsx_error)?;
+            }
+        }
+
+        workbook.save(path).map_err(xlsx_error)
+    }
+}
+
+fn cell_kind(type_name: &str) -> CellKind {
+    let mut type_name = type_name;
+    for wrapper in ["LowCardinality(", "Nullable("] {
+        if let Some(inner) = type_name.strip_prefix(wrapper) {
+            type_name = inner.strip_suffix(')').unwrap_or(inner);
+        }
+    }
+
+    if type_name.starts_with("Int") || type_name.starts_with("UInt") {
+        CellKind::Integer
+    } else if type_name.starts_with("Float") || type_name.starts_with("Decimal") {
+        CellKind::Number
+    } else if type_name == "Bool" {
+        CellKind::Boolean
+    } else if type_name == "Date" || type_name == "Date32" {
+        CellKind::Date
+    } else if type_name.starts_with("DateTime") {
+        CellKind::DateTime
+    } else {
+        CellKind::Text
+    }
+}
+
+fn xlsx_error(error: XlsxError) -> Error {
+    Error::InvalidData(format!("xlsx export failed: {}", error))
+}
@@ -0,0 +1,22 @@
+use chdb_rust::config::EngineConfig;
+
+#[test]
+fn engine_config_xml() {
+    let xml = EngineConfig::new()
+        .with_value("merge_tree.parts_to_throw_insert", 600)
+        .with_tmp_path("/tmp/spill")
+        .unwrap()
+        .with_value("merge_tree.max_suspicious_broken_parts", "1<2")
+        .to_xml();
+
+    assert_eq!(
+        xml,
+        "<clickhouse>\n\
+         \x20   <merge_tree>\n\
+         \x20       <max_suspicious_broken_parts>1&lt;2</max_suspicious_broken_parts>\n\
+         \x20       <parts_to_throw_insert>600</parts_to_throw_insert>\n\
+         \x20   </merge_tree>\n\
+         \x20   <tmp_path>/tmp/spill/</tmp_path>\n\
+         </clickhouse>\n"
+    );
+}
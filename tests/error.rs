@@ -0,0 +1,33 @@
+use chdb_rust::error::Error;
+
+#[test]
+fn render_pretty() {
+    let error = Error::QueryError(
+        "Code: 62. DB::Exception: Syntax error (Multi-statements are not allowed): \
+         failed at position 9 (';') (line 1, col 9): ; SELECT 2. (SYNTAX_ERROR)"
+            .to_string(),
+    );
+
+    assert_eq!(
+        error.render_pretty("SELECT 1; SELECT 2"),
+        format!(
+            "error: {}\n  |\n1 | SELECT 1; SELECT 2\n  |         ^\n\
+             hint: the query holds several statements, run it with `Session::execute_script` or \
+             pass `Arg::MultiQuery`\n",
+            error
+        )
+    );
+}
+
+#[test]
+fn unknown_name_suggestions() {
+    let error = Error::UnknownName {
+        message: "Code: 60. DB::Exception: Unknown table expression identifier 'evnts' in scope \
+                  SELECT * FROM evnts. (UNKNOWN_TABLE)"
+            .to_string(),
+        suggestions: vec!["events".to_string(), "event".to_string()],
+    };
+
+    assert_eq!(error.code(), Some(60));
+    assert!(error.to_string().ends_with("(did you mean events, event?)"));
+}
@@ -55,7 +55,6 @@ fn stateful() {
             "SELECT * FROM logs",
             Some(&[Arg::OutputFormat(OutputFormat::JSONEachRow)]),
         )
-        .unwrap()
         .unwrap();
 
     assert_eq!(result.data_utf8_lossy(), "{\"id\":1,\"msg\":\"test\"}\n");
@@ -72,8 +71,90 @@ fn stateless() {
         &query,
         Some(&[Arg::OutputFormat(OutputFormat::JSONEachRow)]),
     )
-    .unwrap()
     .unwrap();
 
     assert_eq!(result.data_utf8_lossy(), "{\"id\":1,\"msg\":\"test\"}\n");
 }
+
+#[test]
+fn infer_schema() {
+    let schema =
+        chdb_rust::schema::infer_from_file("tests/logs.csv", InputFormat::CSVWithNames).unwrap();
+
+    assert_eq!(
+        schema
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>(),
+        ["id", "msg"]
+    );
+}
+
+#[test]
+fn csv_dialect() {
+    let options = chdb_rust::settings::CsvOptions::new()
+        .with_delimiter(';')
+        .with_null_representation("NULL");
+    let mut args = options.to_args();
+    args.push(Arg::OutputFormat(OutputFormat::CSV));
+
+    let result = execute("SELECT 1 AS a, NULL AS b", Some(&args)).unwrap();
+
+    assert_eq!(result.data_utf8_lossy(), "1;NULL\n");
+}
+
+#[test]
+fn null_representation() {
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-null")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let options = chdb_rust::settings::ExecuteOptions::new()
+        .null_as(Some("NULL"))
+        .with_arg(Arg::OutputFormat(OutputFormat::TabSeparated));
+    let result = session
+        .execute_with("SELECT NULL, '\\\\N', 'null'", &options)
+        .unwrap();
+
+    assert_eq!(
+        result.tsv_rows(None).unwrap(),
+        vec![vec![
+            None,
+            Some("\\N".to_string()),
+            Some("null".to_string())
+        ]]
+    );
+}
+
+#[test]
+fn external_table() {
+    use chdb_rust::schema::Column;
+    use chdb_rust::schema::Schema;
+
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-external")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let schema = Schema {
+        columns: vec![Column {
+            name: "id".to_string(),
+            type_name: "UInt64".to_string(),
+        }],
+    };
+    let options = chdb_rust::settings::ExecuteOptions::new()
+        .with_external_table("ids", &schema, "3\n5\n", InputFormat::TabSeparated)
+        .with_arg(Arg::OutputFormat(OutputFormat::CSV));
+    let result = session
+        .execute_with(
+            "SELECT number FROM numbers(10) WHERE number IN (SELECT id FROM ids)",
+            &options,
+        )
+        .unwrap();
+
+    assert_eq!(result.data_utf8_lossy(), "3\n5\n");
+}
@@ -0,0 +1,91 @@
+use chdb_rust::explain::TableIndexUsage;
+
+#[test]
+fn index_usage_from_explain() {
+    let plan = "Expression ((Project names + Projection))
+  Expression ((Before ORDER BY + (Change column names to column identifiers + Project names)))
+    ReadFromMergeTree (default.events)
+    Indexes:
+      PrimaryKey
+        Keys:
+          user_id
+        Condition: (user_id in [42, 42])
+        Parts: 1/3
+        Granules: 2/120
+      Skip
+        Name: ts_minmax
+        Description: minmax GRANULARITY 1
+        Parts: 1/1
+        Granules: 1/2";
+
+    let usage = TableIndexUsage::from_explain(plan).unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].table, "default.events");
+
+    let primary = &usage[0].indexes[0];
+    assert_eq!(primary.kind, "PrimaryKey");
+    assert_eq!(primary.keys, vec!["user_id".to_string()]);
+    assert_eq!(primary.condition.as_deref(), Some("(user_id in [42, 42])"));
+    assert_eq!((primary.parts_selected, primary.parts_total), (1, 3));
+    assert_eq!(
+        (primary.granules_selected, primary.granules_total),
+        (2, 120)
+    );
+
+    let skip = &usage[0].indexes[1];
+    assert_eq!(skip.name.as_deref(), Some("ts_minmax"));
+    assert_eq!((skip.granules_selected, skip.granules_total), (1, 2));
+}
+
+#[test]
+fn key_recommendation() {
+    use chdb_rust::advisor::KeyRecommendation;
+    use chdb_rust::schema::Column;
+    use chdb_rust::schema::Schema;
+
+    let column = |name: &str, type_name: &str| Column {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+    };
+    let schema = Schema {
+        columns: vec![
+            column("tenant", "String"),
+            column("ts", "DateTime"),
+            column("level", "UInt8"),
+        ],
+    };
+    let queries = [
+        "SELECT count() FROM logs WHERE tenant = 'a' AND ts >= now() - 3600",
+        "SELECT * FROM logs WHERE tenant IN ('a', 'b') AND level > 3 ORDER BY ts LIMIT 10",
+        "SELECT level FROM logs WHERE toDate(ts) = today() AND logs.tenant = 'c'",
+    ]
+    .map(String::from);
+
+    let recommendation = KeyRecommendation::from_queries(&schema, &queries);
+    assert_eq!(recommendation.order_by, vec!["tenant", "ts"]);
+    assert_eq!(recommendation.partition_by.as_deref(), Some("toYYYYMM(ts)"));
+    assert_eq!(recommendation.filter_columns[0].name, "tenant");
+    assert_eq!(recommendation.filter_columns[0].equality_queries, 3);
+}
+
+#[test]
+fn key_recommendation_requires_query_log() {
+    use chdb_rust::error::Error;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-advisor-no-log")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    session
+        .execute(
+            "CREATE TABLE logs (ts DateTime) ENGINE = MergeTree ORDER BY ts",
+            None,
+        )
+        .unwrap();
+
+    assert!(matches!(
+        session.table("logs").recommend_keys(),
+        Err(Error::QueryLogUnavailable)
+    ));
+}
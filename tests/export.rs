@@ -0,0 +1,19 @@
+use chdb_rust::export::validate_csv;
+
+#[test]
+fn rfc4180_validation() {
+    assert!(validate_csv(
+        b"id,msg\r\n1,\"a \"\"b\"\", c\"\r\n2,\"multi\r\nline\"\r\n",
+        b',',
+        true
+    )
+    .is_ok());
+    assert!(validate_csv(b"id,msg\n1,a\n2,", b',', false).is_ok());
+
+    let error = |data: &[u8], crlf| validate_csv(data, b',', crlf).unwrap_err().to_string();
+    assert!(error(b"id,msg\n1,a\n2\n", false).contains("record 3: 1 fields, the header has 2"));
+    assert!(error(b"id,msg\n1,a\"b\n", false).contains("record 2: quote in an unquoted field"));
+    assert!(error(b"id,msg\n1,\"a\"b\n", false).contains("characters after a closing quote"));
+    assert!(error(b"id,msg\n1,\"a\n", false).contains("unterminated quoted field"));
+    assert!(error(b"id,msg\n", true).contains("record 1: record ends with LF"));
+}
@@ -0,0 +1,45 @@
+use chdb_rust::kv::KvStore;
+use chdb_rust::session::SessionBuilder;
+
+#[test]
+fn kv_store() {
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-kv")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let store = KvStore::open(&session, "kv").unwrap();
+
+    store.put("user:1", "alice").unwrap();
+    store.put("user:2", "bob").unwrap();
+    store.put("user:2", "bob's\tnew\nname").unwrap();
+    store.put("order:1", "book").unwrap();
+
+    assert_eq!(store.get("user:1").unwrap().as_deref(), Some("alice"));
+    assert_eq!(
+        store.get("user:2").unwrap().as_deref(),
+        Some("bob's\tnew\nname")
+    );
+
+    store.delete("user:1").unwrap();
+    assert_eq!(store.get("user:1").unwrap(), None);
+
+    assert_eq!(
+        store.scan_prefix("user:").unwrap(),
+        vec![("user:2".to_string(), "bob's\tnew\nname".to_string())]
+    );
+}
+
+#[test]
+fn kv_empty_value() {
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-kv-empty")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let store = KvStore::open(&session, "kv").unwrap();
+
+    store.put("empty", "").unwrap();
+    assert_eq!(store.get("empty").unwrap().as_deref(), Some(""));
+}
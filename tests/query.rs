@@ -0,0 +1,116 @@
+use chdb_rust::query::Param;
+use chdb_rust::query::Query;
+
+#[test]
+fn template_rendering() {
+    let sql = Query::template(
+        "SELECT * FROM {table:Identifier} WHERE ts > {since:DateTime} AND level = {level:UInt8} AND msg != '{not:String}'",
+    )
+    .unwrap()
+    .bind("table", Param::Identifier("logs".into()))
+    .bind("since", "2024-01-01 00:00:00")
+    .bind("level", 3u64)
+    .render()
+    .unwrap();
+
+    assert_eq!(
+        sql,
+        "SELECT * FROM `logs` WHERE ts > CAST('2024-01-01 00:00:00' AS DateTime) AND level = 3 AND msg != '{not:String}'"
+    );
+
+    assert!(Query::template("SELECT {level:UInt8}")
+        .unwrap()
+        .bind("level", 300u64)
+        .render()
+        .is_err());
+}
+
+#[test]
+fn id_set_rendering() {
+    let query = Query::template("SELECT * FROM t WHERE id IN {ids:IdSet}").unwrap();
+
+    let small = query.clone().bind("ids", vec![1u64, 2, 3]);
+    assert_eq!(
+        small.render().unwrap(),
+        "SELECT * FROM t WHERE id IN (1, 2, 3)"
+    );
+
+    let ids: Vec<u64> = (0..20_000).collect();
+    let large = query.bind("ids", ids);
+    let (sql, _) = large
+        .render_with_options(&chdb_rust::settings::ExecuteOptions::new())
+        .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id IN (SELECT id FROM `_chdb_ids_ids`)"
+    );
+}
+
+#[test]
+fn id_set_reuse_and_statements_without_select() {
+    use chdb_rust::settings::ExecuteOptions;
+
+    let ids: Vec<u64> = (0..20_000).collect();
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-id-set")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    // the same placeholder twice reads one external table
+    let twice = Query::template(
+        "SELECT count() FROM numbers(30000) WHERE number IN {ids:IdSet} AND number + 1 IN {ids:IdSet}",
+    )
+    .unwrap()
+    .bind("ids", ids.clone());
+    let result = session
+        .execute_query(
+            &twice,
+            &ExecuteOptions::new().with_arg(chdb_rust::arg::Arg::OutputFormat(
+                chdb_rust::format::OutputFormat::TabSeparated,
+            )),
+        )
+        .unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "19999\n");
+
+    // ALTER can't read external tables, the set is inlined
+    let delete = Query::template("ALTER TABLE t DELETE WHERE id IN {ids:IdSet}")
+        .unwrap()
+        .bind("ids", ids);
+    let (sql, _) = delete.render_with_options(&ExecuteOptions::new()).unwrap();
+    assert!(sql.starts_with("ALTER TABLE t DELETE WHERE id IN (0, 1, 2, "));
+
+    let schema = chdb_rust::schema::Schema {
+        columns: Vec::new(),
+    };
+    let options = ExecuteOptions::new().with_external_table(
+        "ids",
+        &schema,
+        "1\n",
+        chdb_rust::format::InputFormat::TabSeparated,
+    );
+    assert!(matches!(
+        session.execute_with("ALTER TABLE t DELETE WHERE id IN ids", &options),
+        Err(chdb_rust::error::Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn query_over_named_sources() {
+    use chdb_rust::arg::Arg;
+    use chdb_rust::catalog::Source;
+    use chdb_rust::format::OutputFormat;
+
+    let result = chdb_rust::query_over_named(
+        &[("logs", Source::Csv("tests/logs.csv".into()))],
+        "SELECT msg, '{logs}' FROM {logs} -- not {missing}\n /* {missing} */",
+        Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+    )
+    .unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "test\t{logs}\n");
+
+    assert!(matches!(
+        chdb_rust::query_over_named(&[], "SELECT * FROM {logs}", None),
+        Err(chdb_rust::error::Error::TemplateError(_))
+    ));
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "test-utils")]
+
+use std::time::Duration;
+
+use chdb_rust::query_result::QueryResult;
+use chdb_rust::query_result::QueryStats;
+
+#[test]
+fn mock_result() {
+    let stats = QueryStats {
+        rows_read: 2,
+        bytes_read: 16,
+        elapsed: Duration::from_millis(5),
+    };
+    let result = QueryResult::from_bytes_for_tests("a\t\\N\nb\tc\n", stats);
+
+    assert_eq!(result.stats(), stats);
+    assert_eq!(
+        result.tsv_rows(None).unwrap(),
+        vec![
+            vec![Some("a".to_string()), None],
+            vec![Some("b".to_string()), Some("c".to_string())],
+        ]
+    );
+    assert_eq!(result.into_string().unwrap(), "a\t\\N\nb\tc\n");
+}
+
+#[test]
+fn diff_results() {
+    let a =
+        QueryResult::from_bytes_for_tests("id\tname\n1\ta\n2\tb\n3\tc\n", QueryStats::default());
+    let b =
+        QueryResult::from_bytes_for_tests("id\tname\n1\ta\n2\tB\n4\td\n", QueryStats::default());
+
+    let diff = chdb_rust::diff(&a, &b, &["id"]).unwrap();
+    let row = |id: &str, name: &str| vec![Some(id.to_string()), Some(name.to_string())];
+
+    assert_eq!(diff.added, vec![row("4", "d")]);
+    assert_eq!(diff.removed, vec![row("3", "c")]);
+    assert_eq!(diff.changed, vec![(row("2", "b"), row("2", "B"))]);
+}
+
+#[test]
+fn truncated_info() {
+    let json = "{\n\t\"meta\":\n\t[\n\t\t{\n\t\t\t\"name\": \"rows\",\n\t\t\t\"type\": \"UInt64\"\n\t\t}\n\t],\n\n\
+                \t\"data\":\n\t[\n\t\t{\n\t\t\t\"rows\": 7\n\t\t}\n\t],\n\n\
+                \t\"rows\": 1,\n\n\t\"rows_before_limit_at_least\": 42,\n\n\
+                \t\"statistics\":\n\t{\n\t\t\"elapsed\": 0.001\n\t}\n}\n";
+    let info = QueryResult::from_bytes_for_tests(json, QueryStats::default())
+        .truncated_info()
+        .unwrap();
+
+    assert_eq!(info.rows, 1);
+    assert_eq!(info.rows_before_limit_at_least, 42);
+    assert!(info.is_truncated());
+
+    let tsv = QueryResult::from_bytes_for_tests("7\n", QueryStats::default());
+    assert_eq!(tsv.truncated_info(), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn totals_and_extremes() {
+    let json = r#"{
+	"meta": [{"name": "k", "type": "String"}, {"name": "c", "type": "UInt64"}],
+	"data": [["a", 2], ["b", 3]],
+	"totals": ["", 5],
+	"extremes": {"min": ["a", 2], "max": ["b", 3]},
+	"rows": 2
+}"#;
+    let result = QueryResult::from_bytes_for_tests(json, QueryStats::default());
+
+    assert_eq!(result.totals().unwrap(), Some(serde_json::json!(["", 5])));
+    let extremes = result.extremes().unwrap().unwrap();
+    assert_eq!(extremes.min, serde_json::json!(["a", 2]));
+    assert_eq!(extremes.max, serde_json::json!(["b", 3]));
+
+    let plain =
+        QueryResult::from_bytes_for_tests(r#"{"data": [], "rows": 0}"#, QueryStats::default());
+    assert_eq!(plain.totals().unwrap(), None);
+    assert_eq!(plain.extremes().unwrap(), None);
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn xlsx_export() {
+    let result = QueryResult::from_bytes_for_tests(
+        "id\tname\tday\tok\nUInt64\tNullable(String)\tDate\tBool\n1\ta\t2024-01-31\ttrue\n2\t\\N\t2024-02-01\tfalse\n",
+        QueryStats::default(),
+    );
+    let path = std::env::temp_dir().join("chdb-rust-export.xlsx");
+
+    result.write_xlsx(&path).unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(b"PK"));
+    std::fs::remove_file(&path).unwrap();
+
+    let tsv = QueryResult::from_bytes_for_tests("1\n", QueryStats::default());
+    assert!(tsv.write_xlsx(&path).is_err());
+}
+
+#[test]
+fn clone_and_drop_both() {
+    let engine = chdb_rust::execute("SELECT 1", None).unwrap();
+    let copy = engine.clone();
+    assert_eq!(copy.data_ref(), engine.data_ref());
+    assert_eq!(copy.stats(), engine.stats());
+    drop(engine);
+    drop(copy);
+
+    let owned = QueryResult::from_bytes_for_tests("a\n", QueryStats::default());
+    let copy = owned.clone();
+    drop(owned);
+    assert_eq!(copy.data_ref(), b"a\n");
+}
+
+#[test]
+fn tsv_single_empty_field() {
+    let rows = |data: &str| {
+        QueryResult::from_bytes_for_tests(data, QueryStats::default())
+            .tsv_rows(None)
+            .unwrap()
+    };
+
+    assert_eq!(rows(""), Vec::<Vec<Option<String>>>::new());
+    assert_eq!(rows("\n"), vec![vec![Some(String::new())]]);
+    assert_eq!(
+        rows("\n\n"),
+        vec![vec![Some(String::new())], vec![Some(String::new())]]
+    );
+}
+
+#[test]
+fn results_move_across_threads() {
+    fn assert_send<T: Send + 'static>() {}
+    assert_send::<QueryResult>();
+
+    let result = chdb_rust::execute("SELECT 1", None).unwrap();
+    let expected = result.data_ref().to_vec();
+    let moved = std::thread::spawn(move || result.data_ref().to_vec())
+        .join()
+        .unwrap();
+    assert_eq!(moved, expected);
+}
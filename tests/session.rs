@@ -0,0 +1,457 @@
+use std::sync::Arc;
+
+use chdb_rust::session::set_registry_root;
+use chdb_rust::session::Session;
+
+#[test]
+fn named_sessions() {
+    set_registry_root("/tmp/chdb-registry");
+
+    let a = Session::named("analytics").unwrap();
+    let b = Session::named("analytics").unwrap();
+    assert!(Arc::ptr_eq(&a, &b));
+
+    assert!(Session::named("../escape").is_err());
+}
+
+#[test]
+fn cleanup_stale_sessions() {
+    let root = std::path::Path::new("/tmp/chdb-stale");
+    std::fs::create_dir_all(root.join("old")).unwrap();
+    std::fs::write(root.join("old/.chdb-rust-session"), "").unwrap();
+    std::fs::create_dir_all(root.join("foreign")).unwrap();
+
+    let removed = chdb_rust::session::cleanup_stale(root, std::time::Duration::ZERO).unwrap();
+
+    assert_eq!(removed, vec![root.join("old")]);
+    assert!(!root.join("old").exists());
+    assert!(root.join("foreign").exists());
+}
+
+#[test]
+fn cleanup_keeps_foreign_dir() {
+    let path = std::path::Path::new("/tmp/chdb-foreign");
+    std::fs::create_dir_all(path).unwrap();
+    std::fs::write(path.join("precious.txt"), "user data").unwrap();
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path(path)
+        .with_cleanup_policy(chdb_rust::session::CleanupPolicy::OnClose)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        session.close(),
+        Err(chdb_rust::error::Error::RefusingToDeleteForeignDir(_))
+    ));
+    assert!(path.join("precious.txt").exists());
+}
+
+#[test]
+fn open_existing_requires_data() {
+    let result = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-missing")
+        .open_existing()
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(chdb_rust::error::Error::NoExistingData(_))
+    ));
+}
+
+#[test]
+fn disk_quota_blocks_inserts() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-quota")
+        .with_max_disk_usage(4)
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    std::fs::write("/tmp/chdb-quota/filler", "12345").unwrap();
+
+    assert!(matches!(
+        session.execute("INSERT INTO t VALUES (1)", None),
+        Err(chdb_rust::error::Error::DiskQuotaExceeded { used: 5, limit: 4 })
+    ));
+}
+
+#[test]
+fn quota_limits_queries_per_caller() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-quota")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let quotas = chdb_rust::quota::QuotaManager::new().with_queries_per_minute(2);
+
+    quotas
+        .execute(&session, "plugin", "SELECT 1", None)
+        .unwrap();
+    quotas
+        .execute(&session, "plugin", "SELECT 1", None)
+        .unwrap();
+    let err = quotas.execute(&session, "plugin", "SELECT 1", None);
+    assert!(matches!(
+        err,
+        Err(chdb_rust::error::Error::QuotaExceeded(_))
+    ));
+    assert_eq!(quotas.usage("plugin").queries, 2);
+
+    quotas.execute(&session, "other", "SELECT 1", None).unwrap();
+    quotas.reset("plugin");
+    quotas
+        .execute(&session, "plugin", "SELECT 1", None)
+        .unwrap();
+}
+
+#[test]
+fn audit_log_records_statements() {
+    use chdb_rust::audit::AuditSink;
+    use chdb_rust::settings::ExecuteOptions;
+
+    let path = std::path::Path::new("/tmp/chdb-audit.tsv");
+    let _ = std::fs::remove_file(path);
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-audit")
+        .with_auto_cleanup(true)
+        .with_audit(AuditSink::File(path.to_path_buf()))
+        .build()
+        .unwrap();
+
+    session
+        .execute_with(
+            "SELECT 'tab\there'",
+            &ExecuteOptions::new().comment("plugin:a"),
+        )
+        .unwrap();
+
+    let entries = session.audit_entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].caller, "plugin:a");
+    assert_eq!(entries[0].query, "SELECT 'tab\there'");
+    assert!(entries[0].success);
+}
+
+#[test]
+fn oversized_queries() {
+    use chdb_rust::arg::Arg;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-query-size")
+        .with_arg(Arg::Custom("max_query_size".into(), Some("256".into())))
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let select = format!("SELECT 1 WHERE 1 IN ({})", vec!["1"; 200].join(", "));
+    assert!(matches!(
+        session.execute(&select, None),
+        Err(chdb_rust::error::Error::QueryTooLarge { limit: 256, .. })
+    ));
+
+    session
+        .execute(
+            "CREATE TABLE t (id UInt64, s String) ENGINE = MergeTree ORDER BY id",
+            None,
+        )
+        .unwrap();
+    let count = || {
+        session
+            .execute(
+                "SELECT count() FROM t",
+                Some(&[Arg::OutputFormat(
+                    chdb_rust::format::OutputFormat::TabSeparated,
+                )]),
+            )
+            .unwrap()
+            .data_utf8()
+            .unwrap()
+    };
+
+    // VALUES data doesn't count toward max_query_size, the insert is sent whole
+    let mut rows: Vec<String> = (0..100).map(|i| format!("({}, 'a,(b)')", i)).collect();
+    session
+        .execute(&format!("INSERT INTO t VALUES {}", rows.join(", ")), None)
+        .unwrap();
+    assert_eq!(count(), "100\n");
+
+    // a bad row fails the whole insert instead of leaving earlier rows behind
+    rows.push("('not a number', 'x')".to_string());
+    assert!(session
+        .execute(&format!("INSERT INTO t VALUES {}", rows.join(", ")), None)
+        .is_err());
+    assert_eq!(count(), "100\n");
+}
+
+#[test]
+fn blocking_handle() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-blocking")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let handle = chdb_rust::blocking::Handle::new(session).unwrap();
+
+    handle.execute("SELECT 1", Vec::new()).unwrap();
+}
+
+#[test]
+fn query_complete_hook() {
+    let completed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = completed.clone();
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-query-complete")
+        .with_auto_cleanup(true)
+        .on_query_complete(move |query| {
+            recorded.lock().unwrap().push((
+                query.query.to_string(),
+                query.error.is_some(),
+                query.stats.rows_read,
+            ));
+        })
+        .build()
+        .unwrap();
+
+    session.execute("SELECT 1", None).unwrap();
+    assert!(session.execute("SELECT * FROM missing", None).is_err());
+    assert_eq!(
+        *completed.lock().unwrap(),
+        vec![
+            ("SELECT 1".to_string(), false, 1),
+            ("SELECT * FROM missing".to_string(), true, 0),
+        ]
+    );
+
+    let error = chdb_rust::error::Error::QueryError(
+        "Code: 60. DB::Exception: Unknown table expression identifier 'missing'".to_string(),
+    );
+    assert_eq!(error.code(), Some(60));
+}
+
+#[test]
+fn result_transforms() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-transform")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap()
+        .with_transform(|_: &str, result: chdb_rust::query_result::QueryResult| {
+            Ok(result.with_data("redacted\n"))
+        });
+
+    let result = session.execute("SELECT secret FROM users", None).unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "redacted\n");
+}
+
+#[test]
+fn statement_interceptors() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = seen.clone();
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-interceptor")
+        .with_auto_cleanup(true)
+        .on_query_complete(move |query| recorded.lock().unwrap().push(query.query.to_string()))
+        .build()
+        .unwrap()
+        .with_interceptor(|statement: &mut chdb_rust::intercept::Statement| {
+            statement.sql = statement.sql.replace("events", "tenant_a.events");
+            statement.set("log_comment", "tenant_a");
+        });
+
+    session.execute("SELECT count() FROM events", None).unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec!["SELECT count() FROM tenant_a.events".to_string()]
+    );
+}
+
+#[test]
+fn scheduler_runs_jobs() {
+    let runs = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = runs.clone();
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-scheduler")
+        .with_auto_cleanup(true)
+        .on_query_complete(move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        })
+        .build()
+        .unwrap();
+
+    let handle = chdb_rust::scheduler::Scheduler::new(std::sync::Arc::new(session))
+        .with_job("rollup", "SELECT 1", std::time::Duration::from_millis(10))
+        .with_jitter(std::time::Duration::from_millis(2))
+        .start()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    handle.stop();
+
+    let runs = runs.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(runs >= 2, "{} runs", runs);
+}
+
+#[test]
+fn scheduler_keeps_job_errors() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-scheduler-errors")
+        .with_auto_cleanup(true)
+        .with_max_disk_usage(0)
+        .build()
+        .unwrap();
+
+    let handle = chdb_rust::scheduler::Scheduler::new(std::sync::Arc::new(session))
+        .with_job(
+            "ingest",
+            "INSERT INTO t VALUES (1)",
+            std::time::Duration::from_millis(10),
+        )
+        .start()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let errors = handle.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors["ingest"],
+        chdb_rust::error::Error::ScriptFailed { .. }
+    ));
+    handle.stop();
+}
+
+#[test]
+fn builder_settings_last_call_wins() {
+    use chdb_rust::arg::Arg;
+    use chdb_rust::format::OutputFormat;
+    use chdb_rust::settings::Preset;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-merged-settings")
+        .with_memory_soft_limit(512 << 20)
+        .with_preset(Preset::LowMemory)
+        .with_max_threads(3)
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let result = session
+        .execute(
+            "SELECT getSetting('max_threads'), getSetting('max_memory_usage')",
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )
+        .unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "3\t1073741824\n");
+}
+
+#[test]
+fn warm_up_tables() {
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-warm-up")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    session
+        .execute(
+            "CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id",
+            None,
+        )
+        .unwrap();
+    session
+        .execute("INSERT INTO t SELECT number FROM numbers(1000)", None)
+        .unwrap();
+
+    session.warm_up(&["t"]).unwrap();
+    assert!(session.warm_up(&["missing"]).is_err());
+}
+
+#[test]
+fn query_log_requires_configuration() {
+    use chdb_rust::error::Error;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-no-query-log")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        session.queries_by_comment("nothing"),
+        Err(Error::QueryLogUnavailable)
+    ));
+}
+
+#[test]
+fn coordinator_readers_wait_for_the_writer() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use chdb_rust::coordinator::Coordinator;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-coordinator")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let coordinator = Coordinator::new(session);
+
+    let writer = coordinator.write();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let _reader = coordinator.read();
+            sender.send(()).unwrap();
+        });
+
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(writer);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    });
+
+    // readers share the lock
+    let _first = coordinator.read();
+    let _second = coordinator.read();
+}
+
+#[test]
+fn coordinator_readers_cannot_write() {
+    use chdb_rust::coordinator::Coordinator;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-coordinator-readonly")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let coordinator = Coordinator::new(session);
+
+    coordinator
+        .write()
+        .execute(
+            "CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id",
+            None,
+        )
+        .unwrap();
+
+    let reader = coordinator.read();
+    assert!(reader.execute("INSERT INTO t VALUES (1)", None).is_err());
+    assert!(reader.execute("SELECT count() FROM t", None).is_ok());
+}
+
+#[test]
+fn non_fatal_errors_reach_the_error_callback() {
+    use std::sync::Mutex;
+
+    use chdb_rust::audit::AuditSink;
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let recorded = errors.clone();
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-error-callback")
+        .with_auto_cleanup(true)
+        .with_audit(AuditSink::File("/nonexistent/audit.tsv".into()))
+        .on_error(move |e| recorded.lock().unwrap().push(e.to_string()))
+        .build()
+        .unwrap();
+
+    session.execute("SELECT 1", None).unwrap();
+    assert_eq!(errors.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,61 @@
+use chdb_rust::snapshot::normalize;
+use chdb_rust::snapshot::Snapshots;
+
+#[test]
+fn normalize_volatile_fields() {
+    let output = "{\"rows\": 1, \"statistics\": {\"elapsed\": 0.000123, \"rows_read\": 1}, \"query_id\": \"a\\\"b\"}";
+
+    assert_eq!(
+        normalize(output),
+        "{\"rows\": 1, \"statistics\": {\"elapsed\": \"<elapsed>\", \"rows_read\": 1}, \"query_id\": \"<query_id>\"}"
+    );
+}
+
+#[test]
+fn snapshot_mismatch_panics() {
+    let dir = std::path::Path::new("/tmp/chdb-snapshots");
+    let _ = std::fs::remove_dir_all(dir);
+    let snapshots = Snapshots::new(dir);
+
+    snapshots.assert_text("numbers", "csv", "1\n2\n");
+    snapshots.assert_text("numbers", "csv", "1\n2\n");
+
+    let mismatch = std::panic::catch_unwind(|| snapshots.assert_text("numbers", "csv", "1\n3\n"));
+    assert!(mismatch.is_err());
+}
+
+#[test]
+fn normalize_keeps_result_columns() {
+    let output = "{\"data\": [{\"elapsed\": 5, \"query_id\": \"\\\"statistics\\\": {\"}], \
+                  \"statistics\": {\"elapsed\": 0.1}}";
+    assert_eq!(
+        normalize(output),
+        "{\"data\": [{\"elapsed\": 5, \"query_id\": \"\\\"statistics\\\": {\"}], \
+         \"statistics\": {\"elapsed\": \"<elapsed>\"}}"
+    );
+
+    let rows = "{\"query_id\": \"a\", \"elapsed\": 1}\n{\"query_id\": \"b\", \"elapsed\": 2}\n";
+    assert_eq!(normalize(rows), rows);
+}
+
+#[test]
+fn large_snapshot_diff() {
+    let dir = std::path::Path::new("/tmp/chdb-snapshots-large");
+    let _ = std::fs::remove_dir_all(dir);
+    let snapshots = Snapshots::new(dir);
+
+    let lines = |offset: usize| {
+        (0..50_000)
+            .map(|i| format!("{}\n", i + offset))
+            .collect::<String>()
+    };
+    snapshots.assert_text("large", "csv", &format!("header\n{}", lines(0)));
+
+    let mismatch = std::panic::catch_unwind(|| {
+        snapshots.assert_text("large", "csv", &format!("header\n{}", lines(1)))
+    })
+    .unwrap_err();
+    let message = mismatch.downcast_ref::<String>().unwrap();
+    assert!(message.contains("\n  header\n"));
+    assert!(message.contains("\n- 0\n"));
+}
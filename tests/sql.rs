@@ -0,0 +1,246 @@
+use chdb_rust::nested::flatten_query;
+use chdb_rust::schema::Column;
+use chdb_rust::sql::split_statements;
+
+#[test]
+fn split_script() {
+    let script = "CREATE TABLE t (s String) ENGINE = Memory; -- create; table\n\
+                  INSERT INTO t VALUES ('a;b'), (`x;y`);\n\
+                  /* comment; /* nested; */ */ SELECT ';' FROM t;\n\
+                  INSERT INTO t FORMAT CSV \"c;d\"\n\
+                  SELECT 1;;";
+
+    assert_eq!(
+        split_statements(script),
+        vec![
+            "CREATE TABLE t (s String) ENGINE = Memory",
+            "-- create; table\nINSERT INTO t VALUES ('a;b'), (`x;y`)",
+            "/* comment; /* nested; */ */ SELECT ';' FROM t",
+            "INSERT INTO t FORMAT CSV \"c;d\"",
+            "SELECT 1",
+        ]
+    );
+}
+
+#[test]
+fn guard_allowlist() {
+    use chdb_rust::guard::Guard;
+
+    let guard = Guard::read_only().with_database("analytics");
+
+    guard
+        .check("SELECT * FROM events e, analytics.users AS u WHERE e.id = u.id")
+        .unwrap();
+    guard
+        .check("WITH recent AS (SELECT 1) SELECT * FROM recent")
+        .unwrap();
+    guard
+        .check("SELECT extract(DAY FROM ts) FROM events")
+        .unwrap();
+    guard.check("SELECT 'FROM secret.t' FROM events").unwrap();
+
+    assert!(guard.check("DROP TABLE events").is_err());
+    assert!(guard.check("SELECT * FROM events, secret.users").is_err());
+    assert!(guard
+        .check("SELECT * FROM events JOIN system.users USING id")
+        .is_err());
+    assert!(guard.check("SELECT * FROM file('/etc/passwd')").is_err());
+    assert!(guard.check("SELECT 1 WHERE 1 IN secret.ids").is_err());
+    assert!(guard
+        .check("SELECT 1; INSERT INTO events VALUES (1)")
+        .is_err());
+    assert!(guard.check("SELECT 1 SETTINGS readonly = 0").is_err());
+
+    let tables = Guard::read_only().with_table("events");
+    tables.check("SELECT count() FROM events").unwrap();
+    assert!(tables.check("SELECT count() FROM users").is_err());
+}
+
+#[test]
+fn guard_bypasses() {
+    use chdb_rust::guard::Guard;
+    use chdb_rust::guard::StatementKind;
+
+    let guard = Guard::read_only()
+        .with_statement(StatementKind::Create)
+        .with_table("events");
+
+    // a CTE only shadows tables inside its own scope
+    assert!(guard
+        .check("SELECT * FROM secret WHERE 1 IN (WITH secret AS (SELECT 1) SELECT * FROM secret)")
+        .is_err());
+    guard
+        .check("SELECT * FROM events WHERE 1 IN (WITH secret AS (SELECT 1) SELECT * FROM secret)")
+        .unwrap();
+    guard
+        .check("WITH a AS (SELECT 1), b AS (SELECT * FROM a) SELECT * FROM b")
+        .unwrap();
+
+    // tables named in engine arguments and dictionary sources
+    assert!(guard
+        .check("CREATE TABLE events ENGINE = Merge('other', '.*')")
+        .is_err());
+    assert!(guard
+        .check("CREATE TABLE events (x UInt8) ENGINE = Buffer(other, t, 1, 10, 100, 1, 10, 1, 10)")
+        .is_err());
+    assert!(guard
+        .check(
+            "CREATE DICTIONARY events (id UInt64) PRIMARY KEY id \
+             SOURCE(CLICKHOUSE(DB 'other' TABLE 't')) LAYOUT(FLAT()) LIFETIME(0)"
+        )
+        .is_err());
+    assert!(guard.check("SELECT * FROM merge('other', '.*')").is_err());
+    guard
+        .check("CREATE TABLE events (x UInt8) ENGINE = MergeTree ORDER BY x")
+        .unwrap();
+
+    // `EXISTS t` names a table
+    assert!(guard.check("EXISTS secret").is_err());
+    assert!(guard.check("EXISTS TABLE secret").is_err());
+    guard.check("EXISTS events").unwrap();
+}
+
+#[test]
+fn guard_table_lists() {
+    use chdb_rust::guard::Guard;
+
+    let guard = Guard::read_only();
+
+    // comma-separated tables after subqueries, SAMPLE, FINAL and JOIN clauses
+    for query in [
+        "SELECT * FROM (SELECT 1) AS a, system.tables",
+        "SELECT * FROM (SELECT 1) a, system.tables",
+        "SELECT * FROM t SAMPLE 0.1, system.tables",
+        "SELECT * FROM t SAMPLE 1/10 OFFSET 1/2, system.tables",
+        "SELECT * FROM t FINAL SAMPLE 1/10, system.tables",
+        "SELECT * FROM t CROSS JOIN u, system.tables",
+        "SELECT * FROM t LEFT JOIN u ON 1, system.tables",
+        "SELECT * FROM t JOIN u USING (id), system.tables",
+        "SELECT 1 WHERE 1 IN (SELECT 1 FROM t, system.tables)",
+    ] {
+        assert!(guard.check(query).is_err(), "{}", query);
+    }
+
+    guard
+        .check("SELECT a, b FROM t AS x, (SELECT 1) y WHERE x.a IN (1, 2) ORDER BY a, b LIMIT 1, 2")
+        .unwrap();
+    guard
+        .check("SELECT * FROM t ARRAY JOIN tags AS tag, ids AS id")
+        .unwrap();
+}
+
+#[test]
+fn tenant_scope() {
+    use chdb_rust::tenant::TenantSession;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-tenant")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let tenant = TenantSession::for_tenant(&session, "acme").unwrap();
+
+    assert_eq!(tenant.database(), "tenant_acme");
+    assert!(tenant.execute("SELECT * FROM events", None).is_ok());
+    assert!(matches!(
+        tenant.execute("SELECT * FROM tenant_other.events", None),
+        Err(chdb_rust::error::Error::Forbidden(_))
+    ));
+    for escape in [
+        "CREATE TABLE x ENGINE = Merge('tenant_other', '.*')",
+        "CREATE TABLE x (id UInt64) ENGINE = Buffer(tenant_other, events, 1, 10, 100, 1, 10, 1, 10)",
+        "CREATE TABLE x AS tenant_other.events",
+        "CREATE DICTIONARY d (id UInt64) PRIMARY KEY id \
+         SOURCE(CLICKHOUSE(DB 'tenant_other' TABLE 'events')) LAYOUT(FLAT()) LIFETIME(0)",
+        "CREATE TABLE x ENGINE = MergeTree ORDER BY id AS SELECT * FROM tenant_other.events",
+        "RENAME TABLE events TO e2, tenant_other.secrets TO mine",
+        "DROP TABLE events, tenant_other.secrets",
+        "EXCHANGE TABLES events AND tenant_other.secrets",
+        "EXCHANGE DICTIONARIES d AND tenant_other.d",
+        "SELECT * FROM (SELECT 1) a, tenant_other.secrets",
+        "INSERT INTO events SELECT * FROM (SELECT 1) a, tenant_other.secrets",
+        "CREATE VIEW v AS SELECT * FROM (SELECT 1) a, tenant_other.secrets",
+        "SELECT * FROM events CROSS JOIN u, tenant_other.secrets",
+        "SELECT * FROM events LEFT JOIN u ON 1, tenant_other.secrets",
+        "SELECT * FROM events SAMPLE 1, tenant_other.secrets",
+        "SELECT tenant_other.secrets.id FROM events",
+        "CREATE FUNCTION f AS x -> x + 1",
+        "CREATE OR REPLACE FUNCTION f AS x -> x + 1",
+        "DROP FUNCTION IF EXISTS f",
+    ] {
+        assert!(
+            matches!(
+                tenant.execute(escape, None),
+                Err(chdb_rust::error::Error::Forbidden(_))
+            ),
+            "{}",
+            escape
+        );
+    }
+    assert!(TenantSession::for_tenant(&session, "a.b").is_err());
+}
+
+#[test]
+fn remote_table_function() {
+    use chdb_rust::remote::RemoteConfig;
+
+    assert_eq!(
+        RemoteConfig::new("ch-central:9000").table_function("db.events"),
+        "remote('ch-central:9000', `db`.`events`)"
+    );
+    assert_eq!(
+        RemoteConfig::new("ch-central:9440")
+            .with_secure(true)
+            .with_credentials("edge", "s'cret")
+            .table_function("events"),
+        "remoteSecure('ch-central:9440', `events`, 'edge', 's\\'cret')"
+    );
+}
+
+#[test]
+fn catalog_rewrite() {
+    use chdb_rust::catalog::Catalog;
+    use chdb_rust::format::InputFormat;
+
+    let mut catalog = Catalog::new();
+    catalog
+        .add("events", "data/events/*.parquet", InputFormat::Parquet)
+        .add("users", "users.csv", InputFormat::CSVWithNames);
+
+    assert_eq!(
+        catalog.rewrite("SELECT events.ts, u.name FROM events JOIN `users` u ON u.id = events.user_id WHERE 'events' != ''"),
+        "SELECT events.ts, u.name FROM file('data/events/*.parquet', 'Parquet') AS `events` \
+         JOIN file('users.csv', 'CSVWithNames') u ON u.id = events.user_id WHERE 'events' != ''"
+    );
+    assert_eq!(
+        catalog.rewrite("WITH events AS (SELECT 1) SELECT * FROM events, db.users"),
+        "WITH events AS (SELECT 1) SELECT * FROM events, db.users"
+    );
+}
+
+#[test]
+fn flatten_nested_columns() {
+    let column = |name: &str, type_name: &str| Column {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+    };
+    let columns = [
+        column("id", "UInt64"),
+        column("tags", "Array(String)"),
+        column("items.sku", "Array(String)"),
+        column("items.qty", "Array(UInt32)"),
+        column("notes.text", "Array(String)"),
+        column("meta", "Nested(key String, value String)"),
+    ];
+
+    // a lone dotted array like `notes.text` is not a nested column
+    assert_eq!(
+        flatten_query("SELECT * FROM orders", &columns),
+        "SELECT * FROM (SELECT * FROM orders) LEFT ARRAY JOIN `items.sku`, `items.qty` \
+         LEFT ARRAY JOIN `meta`"
+    );
+    assert_eq!(
+        flatten_query("SELECT id, tags FROM orders", &columns[..2]),
+        "SELECT id, tags FROM orders"
+    );
+}
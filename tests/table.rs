@@ -0,0 +1,130 @@
+use chdb_rust::session::SessionBuilder;
+use chdb_rust::vector::Distance;
+
+#[test]
+fn nearest_neighbors_sql() {
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-table")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+
+    let sql = session.table("docs").nearest_neighbors_sql(
+        "embedding",
+        &[0.5, -1.0, 0.25],
+        3,
+        Distance::Cosine,
+    );
+
+    assert_eq!(
+        sql,
+        "SELECT *, cosineDistance(`embedding`, [0.5,-1,0.25]) AS distance FROM `docs` ORDER BY distance LIMIT 3"
+    );
+}
+
+#[test]
+fn search_condition() {
+    assert_eq!(
+        chdb_rust::search::search_condition("msg", "timeout db-01"),
+        "hasToken(`msg`, 'timeout') AND position(`msg`, 'db-01') > 0"
+    );
+}
+
+#[test]
+fn table_builder_codecs() {
+    use chdb_rust::table::Codec;
+    use chdb_rust::table::TableBuilder;
+
+    let sql = TableBuilder::new("metrics")
+        .with_default_codec(&[Codec::Zstd(3)])
+        .with_column_codec("ts", "DateTime", &[Codec::DoubleDelta, Codec::Lz4])
+        .with_column("value", "Float64")
+        .with_order_by("ts")
+        .to_sql();
+
+    assert_eq!(
+        sql,
+        "CREATE TABLE `metrics` (`ts` DateTime CODEC(DoubleDelta, LZ4), `value` Float64 CODEC(ZSTD(3))) \
+         ENGINE = MergeTree ORDER BY ts"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn registered_slice_outlives_the_call() {
+    use chdb_rust::arg::Arg;
+    use chdb_rust::format::OutputFormat;
+
+    #[derive(serde::Serialize)]
+    struct User {
+        id: u64,
+        name: &'static str,
+    }
+
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-register")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    let users = [
+        User {
+            id: 1,
+            name: "alice",
+        },
+        User { id: 2, name: "bob" },
+    ];
+
+    session.register_slice("users", &users).unwrap();
+    // registering again replaces the table instead of failing
+    session.register_slice("users", &users).unwrap();
+
+    let result = session
+        .execute(
+            "SELECT id, name FROM users ORDER BY id",
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )
+        .unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "1\talice\n2\tbob\n");
+}
+
+#[test]
+fn ring_buffer_bounds() {
+    use chdb_rust::arg::Arg;
+    use chdb_rust::format::InputFormat;
+    use chdb_rust::format::OutputFormat;
+    use chdb_rust::ring_buffer::Retention;
+
+    let session = SessionBuilder::new()
+        .with_data_path("/tmp/chdb-ring-buffer")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    session
+        .execute(
+            "CREATE TABLE events (ts UInt64) ENGINE = MergeTree ORDER BY ts",
+            None,
+        )
+        .unwrap();
+    let retention = |rows| Retention::MaxRows {
+        order_by: "ts".to_string(),
+        rows,
+    };
+
+    assert!(session
+        .table("events")
+        .as_ring_buffer(retention(0))
+        .is_err());
+
+    let ring = session
+        .table("events")
+        .as_ring_buffer(retention(1))
+        .unwrap();
+    ring.insert("1\n2\n3\n", InputFormat::TabSeparated).unwrap();
+    let result = session
+        .execute(
+            "SELECT ts FROM events",
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )
+        .unwrap();
+    assert_eq!(result.data_utf8().unwrap(), "3\n");
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use chdb_rust::timeseries::bucket;
+use chdb_rust::timeseries::downsample;
+use chdb_rust::timeseries::Aggregation;
+use chdb_rust::timeseries::Granularity;
+
+#[test]
+fn downsample_with_fill() {
+    let sql = downsample("metrics", "cpu", Duration::from_secs(60), Aggregation::Avg)
+        .with_range("now() - INTERVAL 1 HOUR", "now()")
+        .with_fill(true)
+        .to_sql();
+
+    assert_eq!(
+        sql,
+        "SELECT toStartOfInterval(`ts`, INTERVAL 60 SECOND) AS bucket, avg(`cpu`) AS agg_value \
+         FROM `metrics` WHERE `ts` >= now() - INTERVAL 1 HOUR AND `ts` < now() GROUP BY bucket \
+         ORDER BY bucket WITH FILL FROM toStartOfInterval(now() - INTERVAL 1 HOUR, INTERVAL 60 SECOND) \
+         TO now() STEP INTERVAL 60 SECOND"
+    );
+}
+
+#[test]
+fn rollup_sql() {
+    use chdb_rust::rollup::Rollup;
+
+    let rollup = Rollup::new(
+        "requests",
+        "requests_1m",
+        "service",
+        &[("latency", Aggregation::Avg, "latency_ms")],
+        Duration::from_secs(60),
+    );
+
+    assert_eq!(
+        rollup.refresh_sql(),
+        "INSERT INTO `requests_1m` SELECT toStartOfInterval(`ts`, INTERVAL 60 SECOND) AS bucket, service, \
+         avgState(`latency_ms`) AS `latency` FROM `requests` \
+         WHERE toStartOfInterval(`ts`, INTERVAL 60 SECOND) > (SELECT max(bucket) FROM `requests_1m`) \
+         AND toStartOfInterval(`ts`, INTERVAL 60 SECOND) < toStartOfInterval(now(), INTERVAL 60 SECOND) \
+         GROUP BY bucket, service"
+    );
+    assert_eq!(
+        rollup.query_sql(),
+        "SELECT bucket, service, avgMerge(`latency`) AS `latency` FROM `requests_1m` \
+         GROUP BY bucket, service ORDER BY bucket, service"
+    );
+}
+
+#[test]
+fn auto_bucket() {
+    let to = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let day = Granularity::Auto(to - Duration::from_secs(86400), to, 300);
+    assert_eq!(
+        bucket("ts", day),
+        "toStartOfInterval(`ts`, INTERVAL 300 SECOND)"
+    );
+
+    let year = Granularity::Auto(to - Duration::from_secs(365 * 86400), to, 100);
+    assert_eq!(year.interval_sql(), "INTERVAL 1 WEEK");
+
+    let decades = Granularity::Auto(to - Duration::from_secs(30 * 365 * 86400), to, 10);
+    assert_eq!(decades.interval_sql(), "INTERVAL 1 YEAR");
+
+    assert_eq!(
+        Granularity::Fixed(Duration::from_secs(60)).interval_sql(),
+        "INTERVAL 60 SECOND"
+    );
+}
+
+#[test]
+fn rollup_view_backfills_existing_rows() {
+    use chdb_rust::arg::Arg;
+    use chdb_rust::format::OutputFormat;
+    use chdb_rust::rollup::Rollup;
+
+    let session = chdb_rust::session::SessionBuilder::new()
+        .with_data_path("/tmp/chdb-rollup-backfill")
+        .with_auto_cleanup(true)
+        .build()
+        .unwrap();
+    session
+        .execute(
+            "CREATE TABLE requests (ts DateTime, latency_ms UInt64) ENGINE = MergeTree ORDER BY ts",
+            None,
+        )
+        .unwrap();
+    session
+        .execute(
+            "INSERT INTO requests VALUES ('2024-01-01 00:00:10', 10), ('2024-01-01 00:01:10', 20)",
+            None,
+        )
+        .unwrap();
+
+    let rollup = Rollup::new(
+        "requests",
+        "requests_1m",
+        "",
+        &[("hits", Aggregation::Count, "latency_ms")],
+        Duration::from_secs(60),
+    );
+    rollup.create_materialized_view(&session).unwrap();
+    // a second call neither fails nor aggregates the rows again
+    rollup.create_materialized_view(&session).unwrap();
+    session
+        .execute(
+            "INSERT INTO requests VALUES ('2024-01-01 00:01:20', 30)",
+            None,
+        )
+        .unwrap();
+
+    let result = session
+        .execute(
+            &rollup.query_sql(),
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )
+        .unwrap();
+    assert_eq!(
+        result.data_utf8().unwrap(),
+        "2024-01-01 00:00:00\t1\n2024-01-01 00:01:00\t2\n"
+    );
+}